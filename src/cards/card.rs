@@ -18,6 +18,15 @@ impl Suit {
     pub fn all_suits() -> &'static [Suit] {
         &[Suit::Hearts, Suit::Clubs, Suit::Diamonds, Suit::Spades]
     }
+    // The name in plain ASCII, for text that can't show the suit symbols
+    pub fn name(self) -> &'static str {
+        match self {
+            Suit::Hearts   => "hearts",
+            Suit::Clubs    => "clubs",
+            Suit::Diamonds => "diamonds",
+            Suit::Spades   => "spades",
+        }
+    }
 }
 
 impl fmt::Debug for Suit {
@@ -107,4 +116,16 @@ impl Card {
     pub fn suit(&self) -> Suit {
         Suit::from_u8(self.0 / 13)
     }
+    // A stable encoding of cards as 0..52, suit by suit in the order of
+    // `Suit::all_suits` and from Two to Ace within each suit
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+    pub fn from_index(index: usize) -> Option<Card> {
+        if index < 52 {
+            Some(Card(index as u8))
+        } else {
+            None
+        }
+    }
 }
@@ -1,6 +1,7 @@
 
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng, SeedableRng};
 use super::*;
 
 pub struct Deck {
@@ -18,13 +19,30 @@ impl Deck {
         Deck { cards }
     }
 
-    pub fn shuffle(mut self) -> Self {
-        let mut rng = thread_rng();
+    // The last card is drawn first
+    pub fn from_cards(cards: Vec<Card>) -> Deck {
+        Deck { cards }
+    }
+
+    // Shuffled with a seeded RNG, the same seed always gives the same order
+    pub fn with_seed(seed: u64) -> Deck {
+        Deck::new().shuffle_with(&mut StdRng::seed_from_u64(seed))
+    }
+
+    pub fn shuffle(self) -> Self {
+        self.shuffle_with(&mut thread_rng())
+    }
+
+    pub fn shuffle_with<R: Rng + ?Sized>(mut self, rng: &mut R) -> Self {
         let v = &mut self.cards;
-        v.shuffle(&mut rng);
+        v.shuffle(rng);
         self
     }
 
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
     pub fn is_empty(&self) -> bool {
         self.cards.is_empty()
     }
@@ -1,49 +1,81 @@
 
 use super::*;
 
+// The cards keep the order they were dealt or sorted in, and are also kept
+// as a set, which answers the queries without going through them
 #[derive(Debug, Clone)]
 pub struct Hand {
-    pub cards: Vec<Card>,
+    cards: Vec<Card>,
+    set: CardSet,
 }
 
 impl Hand {
     pub fn new() -> Self {
         Hand {
-            cards: Vec::new()
+            cards: Vec::new(),
+            set: CardSet::new(),
         }
     }
 
+    pub fn from_cards(cards: Vec<Card>) -> Hand {
+        let set = cards.iter().collect();
+        Hand { cards, set }
+    }
+
     pub fn draw_from_deck(deck: &mut Deck, size: usize) -> Hand {
-        Hand {
-            cards: deck.draw_multiple_cards(size),
-        }
+        Hand::from_cards(deck.draw_multiple_cards(size))
     }
 
     pub fn combine(&mut self, mut other: Hand) {
         self.cards.append(&mut other.cards);
+        self.set |= other.set;
     }
 
     pub fn sort(&mut self) {
         self.cards.sort();
     }
 
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.set.contains(card)
+    }
+
+    pub fn push(&mut self, card: Card) {
+        self.cards.push(card);
+        self.set.insert(card);
+    }
+
+    // Returns true if the card was in the hand
+    pub fn remove(&mut self, card: Card) -> bool {
+        self.cards.retain(|c| *c != card);
+        self.set.remove(card)
+    }
+
     pub fn count_of_suit(&self, suit: Suit) -> usize {
-        self.cards.iter()
-            .filter(|c| c.suit() == suit)
-            .count()
+        self.set.count_of_suit(suit)
     }
 
+    // From Two to Ace
     pub fn cards_of_suit(&self, suit: Suit) -> Vec<Card> {
-        self.cards.iter()
-            .filter(|c| c.suit() == suit)
-            .map(|c| *c)
-            .collect()
+        self.set.suit(suit).iter().collect()
+    }
+
+    pub fn to_set(&self) -> CardSet {
+        self.set
     }
 
     pub fn highest_rank_card(&self, suit: Suit) -> Option<Card> {
-        self.cards.iter()
-            .filter(|c| c.suit() == suit)
-            .max_by(|c1, c2| c1.rank().cmp(&c2.rank()))
-            .map(|c| *c)
+        self.set.highest_in_suit(suit)
     }
 }
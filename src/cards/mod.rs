@@ -2,7 +2,13 @@
 mod card;
 mod deck;
 mod hand;
+mod notation;
+mod set;
+#[cfg(test)]
+mod tests;
 
 pub use card::*;
 pub use deck::*;
 pub use hand::*;
+pub use notation::*;
+pub use set::*;
@@ -0,0 +1,194 @@
+
+use super::*;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// Cards are written as rank followed by suit. The default notation is ASCII
+// (`AS`, `TH`), the alternate one (`{:#}`) is Unicode (`A♠`, `10♥`). Parsing
+// accepts both, case-insensitively, as well as `10` and `X` for ten.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseCardError {
+    InvalidSuit(String),
+    InvalidRank(String),
+    InvalidCard(String),
+    DuplicateCard(Card),
+}
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseCardError::InvalidSuit(s) => write!(f, "invalid suit: {:?}", s),
+            ParseCardError::InvalidRank(s) => write!(f, "invalid rank: {:?}", s),
+            ParseCardError::InvalidCard(s) => write!(f, "invalid card: {:?}", s),
+            ParseCardError::DuplicateCard(c) => write!(f, "duplicate card: {}", c),
+        }
+    }
+}
+
+impl Error for ParseCardError {}
+
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            return write!(f, "{:?}", self);
+        }
+        match *self {
+            Suit::Hearts   => write!(f, "H"),
+            Suit::Clubs    => write!(f, "C"),
+            Suit::Diamonds => write!(f, "D"),
+            Suit::Spades   => write!(f, "S"),
+        }
+    }
+}
+
+impl FromStr for Suit {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "H" | "h" | "♥" | "♡" => Ok(Suit::Hearts),
+            "C" | "c" | "♣" | "♧" => Ok(Suit::Clubs),
+            "D" | "d" | "♦" | "♢" => Ok(Suit::Diamonds),
+            "S" | "s" | "♠" | "♤" => Ok(Suit::Spades),
+            _ => Err(ParseCardError::InvalidSuit(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Rank::Ten if f.alternate() => write!(f, "10"),
+            Rank::Ten => write!(f, "T"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl FromStr for Rank {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "A" | "a" => Ok(Rank::Ace),
+            "K" | "k" => Ok(Rank::King),
+            "Q" | "q" => Ok(Rank::Queen),
+            "J" | "j" => Ok(Rank::Jack),
+            "T" | "t" | "X" | "x" | "10" => Ok(Rank::Ten),
+            _ => match s.parse::<u8>() {
+                Ok(x) if (2..=9).contains(&x) => Ok(Rank::from_u8(x)),
+                _ => Err(ParseCardError::InvalidRank(s.to_owned())),
+            }
+        }
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#}{:#}", self.rank(), self.suit())
+        } else {
+            write!(f, "{}{}", self.rank(), self.suit())
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseCardError::InvalidCard(s.to_owned());
+        let (i, _) = s.char_indices().last().ok_or_else(invalid)?;
+        if i == 0 {
+            return Err(invalid());
+        }
+        let rank = s[..i].parse()?;
+        let suit = s[i..].parse()?;
+        Ok(Card::new(rank, suit))
+    }
+}
+
+// Splits a run of ranks such as `AK10x2` into single ranks
+fn parse_ranks(s: &str) -> Result<Vec<Rank>, ParseCardError> {
+    let mut ranks = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let mut end = i + c.len_utf8();
+        if c == '1' {
+            if let Some(&(j, '0')) = chars.peek() {
+                chars.next();
+                end = j + 1;
+            }
+        }
+        ranks.push(s[i..end].parse()?);
+    }
+    Ok(ranks)
+}
+
+// Suits in the order they are written in a hand
+const SUIT_ORDER: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs];
+
+// A hand is written as groups of ranks, each followed by their suit, e.g.
+// `AKQ♠ 93♥ T8642♦ 5♣` or `AKQS 93H T8642D 5C`. Single cards like `AS KS`
+// are just groups of one.
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut first = true;
+        for &suit in SUIT_ORDER.iter() {
+            let cards = self.to_set().suit(suit);
+            if cards.is_empty() {
+                continue;
+            }
+            if !first {
+                write!(f, " ")?;
+            }
+            first = false;
+            for c in cards.iter().rev() {
+                if f.alternate() {
+                    write!(f, "{:#}", c.rank())?;
+                } else {
+                    write!(f, "{}", c.rank())?;
+                }
+            }
+            if f.alternate() {
+                write!(f, "{:#}", suit)?;
+            } else {
+                write!(f, "{}", suit)?;
+            }
+        }
+        if first {
+            write!(f, "-")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Hand {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hand = Hand::new();
+        for group in s.split_whitespace() {
+            if group == "-" {
+                continue;
+            }
+            let (i, _) = group.char_indices().last().expect("non-empty group");
+            let suit: Suit = group[i..].parse()?;
+            let ranks = match &group[..i] {
+                "" => return Err(ParseCardError::InvalidCard(group.to_owned())),
+                "-" => Vec::new(),
+                ranks => parse_ranks(ranks)?,
+            };
+            for rank in ranks {
+                let card = Card::new(rank, suit);
+                if hand.contains(card) {
+                    return Err(ParseCardError::DuplicateCard(card));
+                }
+                hand.push(card);
+            }
+        }
+        Ok(hand)
+    }
+}
@@ -0,0 +1,213 @@
+
+use super::*;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Sub, SubAssign};
+
+const ALL_CARDS: u64 = (1 << 52) - 1;
+const SUIT_MASK: u64 = (1 << 13) - 1;
+
+// A set of cards as a bitset: bit i is the card with `Card::index` i,
+// so the cards of a suit are 13 consecutive bits in rank order.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub fn new() -> Self {
+        CardSet(0)
+    }
+    pub fn all() -> Self {
+        CardSet(ALL_CARDS)
+    }
+    pub fn of_suit(suit: Suit) -> Self {
+        CardSet(SUIT_MASK << (suit as u64 * 13))
+    }
+    pub fn from_bits(bits: u64) -> Self {
+        CardSet(bits & ALL_CARDS)
+    }
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+    pub fn contains(&self, card: Card) -> bool {
+        self.0 & bit(card) != 0
+    }
+    // Returns true if the card was not in the set
+    pub fn insert(&mut self, card: Card) -> bool {
+        let inserted = !self.contains(card);
+        self.0 |= bit(card);
+        inserted
+    }
+    // Returns true if the card was in the set
+    pub fn remove(&mut self, card: Card) -> bool {
+        let removed = self.contains(card);
+        self.0 &= !bit(card);
+        removed
+    }
+
+    pub fn union(self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+    pub fn intersection(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+    pub fn difference(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+
+    pub fn suit(self, suit: Suit) -> CardSet {
+        self.intersection(CardSet::of_suit(suit))
+    }
+    pub fn count_of_suit(&self, suit: Suit) -> usize {
+        self.suit(suit).len()
+    }
+    pub fn highest_in_suit(&self, suit: Suit) -> Option<Card> {
+        self.suit(suit).iter().next_back()
+    }
+    pub fn lowest_in_suit(&self, suit: Suit) -> Option<Card> {
+        self.suit(suit).iter().next()
+    }
+
+    // Suit by suit in the order of `Suit::all_suits`, from Two to Ace
+    // within each suit
+    pub fn iter(&self) -> CardSetIter {
+        CardSetIter(self.0)
+    }
+    // The cards in the same order as `iter`, i.e. a sorted hand
+    pub fn to_hand(&self) -> Hand {
+        Hand::from_cards(self.iter().collect())
+    }
+}
+
+fn bit(card: Card) -> u64 {
+    1 << card.index()
+}
+
+impl fmt::Debug for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CardSetIter(u64);
+
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+        let i = self.0.trailing_zeros();
+        self.0 &= self.0 - 1;
+        Card::from_index(i as usize)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for CardSetIter {
+    fn next_back(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+        let i = 63 - self.0.leading_zeros();
+        self.0 &= !(1 << i);
+        Card::from_index(i as usize)
+    }
+}
+
+impl ExactSizeIterator for CardSetIter {}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> CardSetIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> Self {
+        let mut set = CardSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a> FromIterator<&'a Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = &'a Card>>(iter: I) -> Self {
+        iter.into_iter().cloned().collect()
+    }
+}
+
+impl Extend<Card> for CardSet {
+    fn extend<I: IntoIterator<Item = Card>>(&mut self, iter: I) {
+        for card in iter {
+            self.insert(card);
+        }
+    }
+}
+
+impl<'a> From<&'a Hand> for CardSet {
+    fn from(hand: &'a Hand) -> Self {
+        hand.to_set()
+    }
+}
+
+impl BitOr for CardSet {
+    type Output = CardSet;
+    fn bitor(self, other: CardSet) -> CardSet {
+        self.union(other)
+    }
+}
+
+impl BitAnd for CardSet {
+    type Output = CardSet;
+    fn bitand(self, other: CardSet) -> CardSet {
+        self.intersection(other)
+    }
+}
+
+impl Sub for CardSet {
+    type Output = CardSet;
+    fn sub(self, other: CardSet) -> CardSet {
+        self.difference(other)
+    }
+}
+
+impl Not for CardSet {
+    type Output = CardSet;
+    fn not(self) -> CardSet {
+        CardSet(!self.0 & ALL_CARDS)
+    }
+}
+
+impl BitOrAssign for CardSet {
+    fn bitor_assign(&mut self, other: CardSet) {
+        self.0 |= other.0;
+    }
+}
+
+impl BitAndAssign for CardSet {
+    fn bitand_assign(&mut self, other: CardSet) {
+        self.0 &= other.0;
+    }
+}
+
+impl SubAssign for CardSet {
+    fn sub_assign(&mut self, other: CardSet) {
+        self.0 &= !other.0;
+    }
+}
@@ -0,0 +1,83 @@
+
+use crate::cards::*;
+
+#[test]
+fn card_notation() {
+    let ten = Card::new(Rank::Ten, Suit::Hearts);
+    assert_eq!(ten.to_string(), "TH");
+    assert_eq!(format!("{:#}", ten), "10♥");
+    assert_eq!(format!("{:?}", ten), "X♥");
+    for s in &["TH", "th", "10h", "10♥", "X♡"] {
+        assert_eq!(s.parse::<Card>(), Ok(ten), "{}", s);
+    }
+    assert_eq!("A♠".parse::<Card>(), Ok(Card::new(Rank::Ace, Suit::Spades)));
+    assert_eq!("2c".parse::<Card>(), Ok(Card::new(Rank::Two, Suit::Clubs)));
+    assert!("1H".parse::<Card>().is_err());
+    assert!("AX".parse::<Card>().is_err());
+    assert!("A".parse::<Card>().is_err());
+    assert!("".parse::<Card>().is_err());
+    for i in 0..52 {
+        let card = Card::from_index(i).unwrap();
+        assert_eq!(card.index(), i);
+        assert_eq!(card.to_string().parse::<Card>(), Ok(card));
+        assert_eq!(format!("{:#}", card).parse::<Card>(), Ok(card));
+    }
+    assert_eq!(Card::from_index(52), None);
+}
+
+#[test]
+fn hand_notation() {
+    let hand: Hand = "AKQ♠ 93♥ T8642♦ 5♣".parse().unwrap();
+    assert_eq!(hand.len(), 11);
+    assert_eq!(hand.count_of_suit(Suit::Diamonds), 5);
+    assert_eq!(hand.to_string(), "AKQS 93H T8642D 5C");
+    assert_eq!(format!("{:#}", hand), "AKQ♠ 93♥ 108642♦ 5♣");
+    assert_eq!(format!("{:#}", hand).parse::<Hand>().unwrap().to_string(), hand.to_string());
+    assert_eq!("AS KS 10s".parse::<Hand>().unwrap().to_string(), "AKTS");
+    assert_eq!("-".parse::<Hand>().unwrap().to_string(), "-");
+    assert_eq!("AS as".parse::<Hand>().unwrap_err(), ParseCardError::DuplicateCard(Card::new(Rank::Ace, Suit::Spades)));
+    assert!("AKQ".parse::<Hand>().is_err());
+}
+
+#[test]
+fn card_set() {
+    let hand: Hand = "AQ5S 93H 2D".parse().unwrap();
+    let set = hand.to_set();
+    assert_eq!(set.len(), 6);
+    assert!(set.contains(Card::new(Rank::Queen, Suit::Spades)));
+    assert!(!set.contains(Card::new(Rank::King, Suit::Spades)));
+    assert_eq!(set.count_of_suit(Suit::Spades), 3);
+    assert_eq!(set.count_of_suit(Suit::Clubs), 0);
+    assert_eq!(set.highest_in_suit(Suit::Spades), Some(Card::new(Rank::Ace, Suit::Spades)));
+    assert_eq!(set.lowest_in_suit(Suit::Spades), Some(Card::new(Rank::Five, Suit::Spades)));
+    assert_eq!(set.highest_in_suit(Suit::Clubs), None);
+    assert_eq!(set.suit(Suit::Hearts).iter().collect::<Vec<_>>(), hand.cards_of_suit(Suit::Hearts));
+    assert_eq!(set.iter().rev().count(), 6);
+
+    let mut sorted = hand.clone();
+    sorted.sort();
+    assert_eq!(set.to_hand().cards(), sorted.cards());
+    assert_eq!(set.iter().collect::<CardSet>(), set);
+
+    let mut hand = hand;
+    assert!(hand.remove(Card::new(Rank::Queen, Suit::Spades)));
+    hand.push(Card::new(Rank::King, Suit::Clubs));
+    assert_eq!(hand.to_set(), hand.cards().iter().collect());
+    assert_eq!(hand.count_of_suit(Suit::Spades), 2);
+
+    let spades = CardSet::of_suit(Suit::Spades);
+    assert_eq!(spades.len(), 13);
+    assert_eq!((set & spades).len(), 3);
+    assert_eq!((set - spades).len(), 3);
+    assert_eq!((set | spades).len(), 16);
+    assert_eq!((!set).len(), 46);
+    assert_eq!(!CardSet::all(), CardSet::new());
+    assert_eq!(Suit::all_suits().iter().fold(CardSet::new(), |s, &suit| s | CardSet::of_suit(suit)), CardSet::all());
+
+    let mut set = set;
+    assert!(set.remove(Card::new(Rank::Two, Suit::Diamonds)));
+    assert!(!set.remove(Card::new(Rank::Two, Suit::Diamonds)));
+    assert!(set.insert(Card::new(Rank::Two, Suit::Clubs)));
+    assert!(!set.insert(Card::new(Rank::Two, Suit::Clubs)));
+    assert_eq!(set.len(), 6);
+}
@@ -0,0 +1,113 @@
+
+use super::*;
+use crate::cards::*;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DealError {
+    WrongNumberOfCards(PlayerNumber, usize),
+    DuplicateCard(Card),
+    // one of the first five cards is not in the trump caller's hand
+    NotInHand(Card),
+}
+
+impl fmt::Display for DealError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DealError::WrongNumberOfCards(p, n) =>
+                write!(f, "player {:?} is dealt {} cards instead of 13", p, n),
+            DealError::DuplicateCard(card) =>
+                write!(f, "{:?} is dealt more than once", card),
+            DealError::NotInHand(card) =>
+                write!(f, "{:?} is not in the trump caller's hand", card),
+        }
+    }
+}
+
+impl Error for DealError {}
+
+// The cards of each player in the order they are dealt: five cards to each
+// player starting from the trump caller, then four and four more.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deal {
+    trump_caller: PlayerNumber,
+    hands: [Vec<Card>; 4],
+}
+
+impl Deal {
+    // The trump caller is shown `first_five` before calling the trump suit,
+    // the other players get their cards in the order of their hands.
+    pub fn new(trump_caller: PlayerNumber, hands: [Hand; 4], first_five: [Card; 5]) -> Result<Deal, DealError> {
+        let [h1, h2, h3, h4] = hands;
+        let mut hands = [h1.cards().to_vec(), h2.cards().to_vec(), h3.cards().to_vec(), h4.cards().to_vec()];
+        let mut dealt = [false; 52];
+        for (i, hand) in hands.iter().enumerate() {
+            if hand.len() != 13 {
+                return Err(DealError::WrongNumberOfCards(PlayerNumber::from_index(i), hand.len()));
+            }
+            for card in hand {
+                if dealt[card.index()] {
+                    return Err(DealError::DuplicateCard(*card));
+                }
+                dealt[card.index()] = true;
+            }
+        }
+        let caller_hand = &mut hands[trump_caller.as_index()];
+        for (i, card) in first_five.iter().enumerate() {
+            if first_five[..i].contains(card) {
+                return Err(DealError::DuplicateCard(*card));
+            }
+            if !caller_hand.contains(card) {
+                return Err(DealError::NotInHand(*card));
+            }
+        }
+        caller_hand.retain(|c| !first_five.contains(c));
+        caller_hand.splice(0..0, first_five.iter().cloned());
+        Ok(Deal { trump_caller, hands })
+    }
+
+    // The deal that results from dealing the deck
+    pub fn from_deck(trump_caller: PlayerNumber, deck: &Deck) -> Deal {
+        let mut cards = deck.cards().to_vec();
+        let mut hands = [Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+        let mut p = trump_caller;
+        for &n in DEALING_ROUNDS.iter() {
+            for _ in 0..4 {
+                let index = cards.len().saturating_sub(n);
+                hands[p.as_index()].extend(cards.split_off(index));
+                p.increment();
+            }
+        }
+        Deal { trump_caller, hands }
+    }
+
+    // The deck that deals this deal, the inverse of `Deal::from_deck`
+    pub fn to_deck(&self) -> Deck {
+        let mut chunks = Vec::new();
+        let mut p = self.trump_caller;
+        let mut start = 0;
+        for &n in DEALING_ROUNDS.iter() {
+            for _ in 0..4 {
+                chunks.push(&self.hands[p.as_index()][start..start + n]);
+                p.increment();
+            }
+            start += n;
+        }
+        let cards = chunks.into_iter().rev().flatten().cloned().collect();
+        Deck::from_cards(cards)
+    }
+
+    pub fn trump_caller(&self) -> PlayerNumber { self.trump_caller }
+
+    pub fn hand<N: Into<PlayerNumber>>(&self, p: N) -> &[Card] {
+        &self.hands[p.into().as_index()]
+    }
+
+    pub fn first_five(&self) -> &[Card] {
+        &self.hands[self.trump_caller.as_index()][..5]
+    }
+}
+
+// Number of cards dealt to each player per round
+const DEALING_ROUNDS: [usize; 3] = [5, 4, 4];
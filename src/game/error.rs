@@ -0,0 +1,44 @@
+
+use super::*;
+use crate::cards::*;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HokmError {
+    CardNotInHand(PlayerNumber, Card),
+    // the player has a card of the suit that was led
+    MustFollowSuit(PlayerNumber, Card, Suit),
+    NotPlayersTurn(PlayerNumber),
+    WrongPhase(GameState),
+    GameFinished,
+    UndoNotAllowed,
+    NothingToUndo,
+    // a game record without the deal, which can't be replayed
+    NoDeal,
+}
+
+impl fmt::Display for HokmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HokmError::CardNotInHand(p, card) =>
+                write!(f, "player {:?} does not have {:?}", p, card),
+            HokmError::MustFollowSuit(p, card, suit) =>
+                write!(f, "player {:?} played {:?} but must follow suit {:?}", p, card, suit),
+            HokmError::NotPlayersTurn(p) =>
+                write!(f, "it is not player {:?}'s turn", p),
+            HokmError::WrongPhase(state) =>
+                write!(f, "not allowed while the game is in state {:?}", state),
+            HokmError::GameFinished =>
+                write!(f, "the game is already finished"),
+            HokmError::UndoNotAllowed =>
+                write!(f, "taking back cards is not allowed in this game"),
+            HokmError::NothingToUndo =>
+                write!(f, "no card has been played yet"),
+            HokmError::NoDeal =>
+                write!(f, "the record does not have the deal"),
+        }
+    }
+}
+
+impl Error for HokmError {}
@@ -1,22 +1,52 @@
 
 use super::*;
 use crate::cards::*;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use std::mem;
 
 pub struct Hokm {
-    deck: Deck,
-    players: [PlayerState; 4],
-    trump_suit: Option<Suit>,
-    trump_caller: PlayerNumber,
-    turn: PlayerNumber,
-    trick: Option<Trick>,
-    game_state: GameState,
+    pub(super) seed: Option<u64>,
+    pub(super) deck: Deck,
+    // used for dealing after the trump caller is chosen by an ace draw
+    pub(super) next_deck: Option<Deck>,
+    pub(super) deal: Option<Deal>,
+    pub(super) players: [PlayerState; 4],
+    pub(super) trump_suit: Option<Suit>,
+    pub(super) trump_caller: PlayerNumber,
+    pub(super) turn: PlayerNumber,
+    pub(super) trick: Option<Trick>,
+    // tricks collected so far, in order
+    pub(super) tricks: Vec<CompletedTrick>,
+    pub(super) allow_undo: bool,
+    pub(super) observers: Vec<Box<dyn GameObserver>>,
+    pub(super) face_up_cards: Vec<(PlayerNumber, Card)>,
+    pub(super) game_state: GameState,
 }
 
 impl Hokm {
     pub fn new<N: Into<PlayerNumber>>(trump_caller: N) -> Self {
+        Hokm::with_seed(trump_caller, thread_rng().gen())
+    }
+
+    pub fn with_seed<N: Into<PlayerNumber>>(trump_caller: N, seed: u64) -> Self {
+        let mut hokm = Hokm::with_rng(trump_caller, &mut StdRng::seed_from_u64(seed));
+        hokm.seed = Some(seed);
+        hokm
+    }
+
+    pub fn with_rng<N: Into<PlayerNumber>, R: Rng + ?Sized>(trump_caller: N, rng: &mut R) -> Self {
         let trump_caller = trump_caller.into();
+        Hokm::from_deal(Deal::from_deck(trump_caller, &Deck::new().shuffle_with(rng)))
+    }
+
+    pub fn from_deal(deal: Deal) -> Self {
+        let trump_caller = deal.trump_caller();
         Hokm {
-            deck: Deck::new().shuffle(),
+            seed: None,
+            deck: deal.to_deck(),
+            next_deck: None,
+            deal: Some(deal),
             players: [
                 PlayerState::new(),
                 PlayerState::new(),
@@ -27,19 +57,204 @@ impl Hokm {
             trump_caller,
             turn: trump_caller,
             trick: None,
+            tricks: Vec::new(),
+            allow_undo: true,
+            observers: Vec::new(),
+            face_up_cards: Vec::new(),
             game_state: GameState::DealingInitialFiveCards,
         }
     }
 
-    pub fn play(&mut self, players: [&Player; 4]) -> GameEvent {
-        use GameState::*;
+    // The trump caller is chosen by dealing cards face up, starting from
+    // player one, until someone receives an ace.
+    pub fn with_ace_draw() -> Self {
+        Hokm::with_ace_draw_and_seed(thread_rng().gen())
+    }
+
+    pub fn with_ace_draw_and_seed(seed: u64) -> Self {
+        let mut hokm = Hokm::with_ace_draw_and_rng(&mut StdRng::seed_from_u64(seed));
+        hokm.seed = Some(seed);
+        hokm
+    }
+
+    pub fn with_ace_draw_and_rng<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let mut hokm = Hokm::with_rng(PlayerNumber::One, rng);
+        hokm.next_deck = Some(Deck::new().shuffle_with(rng));
+        hokm.deal = None;
+        hokm.game_state = GameState::DrawingForTrumpCaller;
+        hokm
+    }
+
+    // A seeded game can be recreated with `Hokm::with_seed` or `Hokm::with_ace_draw_and_seed`
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    // The trump caller of the next hand stays if their team has won this
+    // hand, otherwise it passes to the next player.
+    pub fn next_trump_caller(&self) -> Option<PlayerNumber> {
+        let winner = self.determine_winner()?;
+        let mut next = self.trump_caller;
+        if next.team() != winner {
+            next.increment();
+        }
+        Some(next)
+    }
+
+    // Asks the player whose decision the game is waiting for, or advances
+    // the game otherwise. Returns an error if the player's decision is illegal,
+    // and `Waiting` if the player has not decided yet.
+    pub fn play(&mut self, players: [&dyn Player; 4]) -> Result<GameEvent, HokmError> {
+        let player = match self.waiting_for() {
+            Some(player) => player,
+            None => return self.advance(),
+        };
+        let view = self.view(player);
         match self.game_state {
+            GameState::SettingTrumpSuit => {
+                match players[player.as_index()].call_trump_suit(&view) {
+                    Decision::Ready(suit) => self.set_trump_suit(player, suit),
+                    Decision::Pending => Ok(GameEvent::Waiting(player)),
+                }
+            }
+            _ => {
+                match players[player.as_index()].play(&view) {
+                    Decision::Ready(card) => self.play_card(player, card),
+                    Decision::Pending => Ok(GameEvent::Waiting(player)),
+                }
+            }
+        }
+    }
+
+    // Deals cards and collects tricks, i.e. all steps of the game that do
+    // not need a player's decision.
+    pub fn advance(&mut self) -> Result<GameEvent, HokmError> {
+        use GameState::*;
+        if self.waiting_for().is_some() {
+            return Err(HokmError::WrongPhase(self.game_state));
+        }
+        let event = match self.game_state {
+            DrawingForTrumpCaller => draw_for_trump_caller(self),
             DealingInitialFiveCards => deal_initial_five_cards(self),
-            SettingTrumpSuit => set_trump_suit(self, players),
             DealingRestOfCards => deal_rest_of_cards(self),
             SortHands => sort_hands(self),
-            NormalPlay => normal_play(self, players),
-            Finished => GameEvent::Won(self.determine_winner().expect("finished w/o winner?!")),
+            NormalPlay => collect_trick(self)?,
+            SettingTrumpSuit => return Err(HokmError::WrongPhase(self.game_state)),
+            Finished => return Err(HokmError::GameFinished),
+        };
+        Ok(self.notify(event))
+    }
+
+    pub fn set_trump_suit(&mut self, player: PlayerNumber, suit: Suit) -> Result<GameEvent, HokmError> {
+        match self.game_state() {
+            GameState::SettingTrumpSuit => {},
+            GameState::Finished => return Err(HokmError::GameFinished),
+            state => return Err(HokmError::WrongPhase(state)),
+        }
+        if player != self.trump_caller {
+            return Err(HokmError::NotPlayersTurn(player));
+        }
+        self.trump_suit = Some(suit);
+        self.game_state = GameState::DealingRestOfCards;
+        Ok(self.notify(GameEvent::SetTrumpSuit(suit)))
+    }
+
+    pub fn play_card(&mut self, player: PlayerNumber, card: Card) -> Result<GameEvent, HokmError> {
+        match self.game_state() {
+            GameState::NormalPlay => {},
+            GameState::Finished => return Err(HokmError::GameFinished),
+            state => return Err(HokmError::WrongPhase(state)),
+        }
+        if self.waiting_for() != Some(player) {
+            return match self.waiting_for() {
+                Some(_) => Err(HokmError::NotPlayersTurn(player)),
+                None => Err(HokmError::WrongPhase(self.game_state)),
+            };
+        }
+        self.check_play(player, card)?;
+        let trick = self.trick.as_mut().ok_or(HokmError::WrongPhase(self.game_state))?;
+        trick.played_cards[player.as_index()] = Some(card);
+        self.players[player.as_index()].hand.remove(card);
+        self.turn.increment();
+        Ok(self.notify(GameEvent::PlayedCard(player, card)))
+    }
+
+    // Takes back the last played card, along with the collection of its
+    // trick if the trick is complete.
+    pub fn undo_card(&mut self) -> Result<GameEvent, HokmError> {
+        if !self.allow_undo {
+            return Err(HokmError::UndoNotAllowed);
+        }
+        match self.game_state {
+            GameState::NormalPlay | GameState::Finished => {},
+            state => return Err(HokmError::WrongPhase(state)),
+        }
+        if self.trick.as_ref().and_then(|t| t.first_card()).is_none() {
+            let completed = self.tricks.pop().ok_or(HokmError::NothingToUndo)?;
+            self.trick = Some(completed.to_trick());
+        }
+        let trick = self.trick.as_mut().ok_or(HokmError::NothingToUndo)?;
+        // the last player in the trick who has played
+        let (mut player, mut p) = (trick.first_player, trick.first_player);
+        for _ in 1..4 {
+            p.increment();
+            if trick.played_cards[p.as_index()].is_some() {
+                player = p;
+            }
+        }
+        let card = trick.played_cards[player.as_index()].take().expect("trick w/o first card?!");
+        let hand = &mut self.players[player.as_index()].hand;
+        hand.push(card);
+        hand.sort();
+        self.turn = player;
+        self.game_state = GameState::NormalPlay;
+        Ok(self.notify(GameEvent::TookBack(player, card)))
+    }
+
+    // Takes back the cards of the current trick, or of the last collected
+    // trick if no card is played in the current one.
+    pub fn undo_trick(&mut self) -> Result<Vec<GameEvent>, HokmError> {
+        let mut events = vec![self.undo_card()?];
+        while self.trick.as_ref().and_then(|t| t.first_card()).is_some() {
+            events.push(self.undo_card()?);
+        }
+        Ok(events)
+    }
+
+    // The observer is notified of every event from now on
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    fn notify(&mut self, event: GameEvent) -> GameEvent {
+        // taken out of the game while they look at it
+        let mut observers = mem::take(&mut self.observers);
+        for observer in observers.iter_mut() {
+            observer.on_event(self, event);
+        }
+        self.observers = observers;
+        event
+    }
+
+    // Tournament games may not allow taking back cards
+    pub fn set_undo_allowed(&mut self, allow: bool) {
+        self.allow_undo = allow;
+    }
+
+    // The game as the player sees it
+    pub fn view(&self, player: PlayerNumber) -> PlayerView<'_> {
+        PlayerView::new(self, player)
+    }
+
+    // The player whose decision (trump suit or card) the game is waiting for
+    pub fn waiting_for(&self) -> Option<PlayerNumber> {
+        match self.game_state() {
+            GameState::SettingTrumpSuit => Some(self.trump_caller),
+            GameState::NormalPlay => match self.trick {
+                Some(ref trick) if !trick.have_all_played() => Some(self.turn),
+                _ => None,
+            },
+            _ => None,
         }
     }
 
@@ -55,37 +270,63 @@ impl Hokm {
         &self.players[p.into().as_index()]
     }
 
-    fn caller(&self) -> &PlayerState  { self.player_state(self.trump_caller) }
-    fn current(&self) -> &PlayerState { self.player_state(self.turn) }
-
+    // Tricks won by each team
     pub fn team_scores(&self) -> (u32, u32) {
         (
-            self.players[0].score + self.players[2].score,
-            self.players[1].score + self.players[3].score
+            self.tricks_won_by(Team::PlayersOneAndThree),
+            self.tricks_won_by(Team::PlayersTwoAndFour)
         )
     }
 
-    fn is_valid_play(&self, player: PlayerNumber, card: Card) -> bool {
+    pub fn tricks_won_by(&self, team: Team) -> u32 {
+        self.tricks.iter().filter(|t| t.winner.team() == team).count() as u32
+    }
+
+    pub fn tricks_won(&self, player: PlayerNumber) -> u32 {
+        self.tricks.iter().filter(|t| t.winner == player).count() as u32
+    }
+
+    // Completed tricks in the order they were played
+    pub fn completed_tricks(&self) -> &[CompletedTrick] {
+        &self.tricks
+    }
+
+    // The cards of the completed tricks and of the current trick
+    pub fn played_cards(&self) -> CardSet {
+        let played = self.tricks.iter().fold(CardSet::new(), |set, t| set | t.card_set());
+        match self.trick {
+            Some(ref trick) => played | trick.cards(),
+            None => played,
+        }
+    }
+
+    pub fn check_play(&self, player: PlayerNumber, card: Card) -> Result<(), HokmError> {
         let hand = &self.players[player.as_index()].hand();
-        if !hand.cards.contains(&card) {
-            return false;
+        if !hand.contains(card) {
+            return Err(HokmError::CardNotInHand(player, card));
         }
-        let trick = match self.trick {
-            Some(ref trick) => trick,
-            None => return false
+        let trick = match self.trick.as_ref() {
+            Some(trick) => trick,
+            None => return Ok(())
         };
-        let first_card = match trick.first_card() {
-            Some(first_card) => first_card,
-            None => return true
-        };
-        if hand.count_of_suit(first_card.suit()) > 0 {
-            return card.suit() == first_card.suit()
+        if !legal_moves(hand, trick).contains(&card) {
+            let first_card = trick.first_card().expect("any card can lead a trick");
+            return Err(HokmError::MustFollowSuit(player, card, first_card.suit()));
+        }
+        Ok(())
+    }
+
+    // The cards the player can play now, none if it is not their turn
+    pub fn legal_moves(&self, player: PlayerNumber) -> Vec<Card> {
+        match (self.waiting_for(), self.game_state(), self.trick.as_ref()) {
+            (Some(p), GameState::NormalPlay, Some(trick)) if p == player =>
+                legal_moves(self.players[player.as_index()].hand(), trick),
+            _ => Vec::new(),
         }
-        true
     }
 
     pub fn game_state(&self) -> GameState {
-        if let Some(_) = self.determine_winner() {
+        if self.determine_winner().is_some() {
             return GameState::Finished;
         }
         self.game_state
@@ -96,43 +337,50 @@ impl Hokm {
     pub fn trump_caller(&self) -> PlayerNumber { self.trump_caller }
     pub fn turn(&self) -> PlayerNumber         { self.turn }
     pub fn trick(&self) -> Option<&Trick>      { self.trick.as_ref() }
+    pub fn undo_allowed(&self) -> bool         { self.allow_undo }
+    // Not known before the trump caller is chosen
+    pub fn deal(&self) -> Option<&Deal>        { self.deal.as_ref() }
+    pub fn face_up_cards(&self) -> &[(PlayerNumber, Card)] { &self.face_up_cards }
 }
 
 pub struct PlayerState {
-    hand: Hand,
-    score: u32,
+    pub(super) hand: Hand,
 }
 
 impl PlayerState {
     pub fn new() -> Self {
         PlayerState {
             hand: Hand::new(),
-            score: 0,
         }
     }
     pub fn deal_cards(&mut self, new_cards: Vec<Card>) {
-        self.hand.combine(Hand { cards: new_cards });
+        self.hand.combine(Hand::from_cards(new_cards));
     }
     pub fn sort_hand(&mut self) {
         self.hand.sort();
     }
     pub fn hand(&self) -> &Hand { &self.hand }
-    pub fn score(&self) -> u32  { self.score }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GameEvent {
+    DrewCard(PlayerNumber, Card),
+    ChoseTrumpCaller(PlayerNumber),
     DealtCards(PlayerNumber, usize),
     SetTrumpSuit(Suit),
     Scored(PlayerNumber),
-    InvalidPlay(PlayerNumber, Card),
     PlayedCard(PlayerNumber, Card),
     Won(Team),
     SortedHands,
+    // a card is taken back into the player's hand
+    TookBack(PlayerNumber, Card),
+    // the player has not decided yet, nothing has happened
+    Waiting(PlayerNumber),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GameState {
+    DrawingForTrumpCaller,
     DealingInitialFiveCards,
     SettingTrumpSuit,
     DealingRestOfCards,
@@ -141,45 +389,56 @@ pub enum GameState {
     Finished,
 }
 
+fn draw_for_trump_caller(hokm: &mut Hokm) -> GameEvent {
+    match hokm.face_up_cards.last() {
+        Some(&(player, card)) if card.rank() == Rank::Ace => {
+            hokm.deck = hokm.next_deck.take().expect("ace draw w/o a deck to deal");
+            hokm.deal = Some(Deal::from_deck(player, &hokm.deck));
+            hokm.trump_caller = player;
+            hokm.turn = player;
+            hokm.game_state = GameState::DealingInitialFiveCards;
+            GameEvent::ChoseTrumpCaller(player)
+        }
+        _ => {
+            let turn = hokm.turn();
+            let card = hokm.deck.draw_card().expect("there are four aces in the deck");
+            hokm.face_up_cards.push((turn, card));
+            hokm.turn.increment();
+            GameEvent::DrewCard(turn, card)
+        }
+    }
+}
+
 fn deal_initial_five_cards(hokm: &mut Hokm) -> GameEvent {
     let turn = hokm.turn();
     let cards = hokm.deck.draw_multiple_cards(5);
-    assert_eq!(cards.len(), 5);
+    debug_assert_eq!(cards.len(), 5);
     hokm.players[turn.as_index()].deal_cards(cards);
     hokm.turn.increment();
     let total_cards_dealt: usize = hokm.players.iter()
-        .map(|p| p.hand.cards.len())
+        .map(|p| p.hand.len())
         .sum();
-    assert!(total_cards_dealt <= 20);
+    debug_assert!(total_cards_dealt <= 20);
     if total_cards_dealt == 20 {
         hokm.game_state = GameState::SettingTrumpSuit;
-        assert_eq!(hokm.turn, hokm.trump_caller);
+        debug_assert_eq!(hokm.turn, hokm.trump_caller);
     }
     GameEvent::DealtCards(turn, 5)
 }
 
-fn set_trump_suit(hokm: &mut Hokm, players: [&Player; 4]) -> GameEvent {
-    assert!(hokm.trump_suit.is_none());
-    let caller = hokm.caller();
-    let trump_suit = players[hokm.turn.as_index()].call_trump_suit(&caller.hand);
-    hokm.trump_suit = Some(trump_suit);
-    hokm.game_state = GameState::DealingRestOfCards;
-    GameEvent::SetTrumpSuit(trump_suit)
-}
-
 fn deal_rest_of_cards(hokm: &mut Hokm) -> GameEvent {
     let turn = hokm.turn();
     let cards = hokm.deck.draw_multiple_cards(4);
-    assert_eq!(cards.len(), 4);
+    debug_assert_eq!(cards.len(), 4);
     hokm.players[turn.as_index()].deal_cards(cards);
     hokm.turn.increment();
     let total_cards_dealt: usize = hokm.players.iter()
-        .map(|p| p.hand.cards.len())
+        .map(|p| p.hand.len())
         .sum();
     if total_cards_dealt == 52 {
         hokm.game_state = GameState::SortHands;
         hokm.trick = Some(Trick::new(hokm.turn()));
-        assert_eq!(hokm.turn, hokm.trump_caller);
+        debug_assert_eq!(hokm.turn, hokm.trump_caller);
     }
     GameEvent::DealtCards(turn, 4)
 }
@@ -192,30 +451,18 @@ fn sort_hands(hokm: &mut Hokm) -> GameEvent {
     GameEvent::SortedHands
 }
 
-fn normal_play(hokm: &mut Hokm, players: [&Player; 4]) -> GameEvent {
+fn collect_trick(hokm: &mut Hokm) -> Result<GameEvent, HokmError> {
     if let Some(team) = hokm.determine_winner() {
         hokm.game_state = GameState::Finished;
-        return GameEvent::Won(team);
-    }
-    let trump_suit = hokm.trump_suit().unwrap();
-    if hokm.trick().unwrap().have_all_played() {
-        let winner = hokm.trick().unwrap().winner(trump_suit).expect("all played");
-        for i in 0..4 {
-            players[i].trick_end(hokm.trick().unwrap());
-        }
-        hokm.players[winner.as_index()].score += 1;
-        hokm.turn = winner;
-        hokm.trick = Some(Trick::new(hokm.turn()));
-        return GameEvent::Scored(winner);
-    }
-    let current = hokm.current();
-    let card = players[hokm.turn.as_index()].play(&current.hand, trump_suit, hokm.trick().unwrap());
-    if !hokm.is_valid_play(hokm.turn(), card) {
-        return GameEvent::InvalidPlay(hokm.turn(), card)
+        return Ok(GameEvent::Won(team));
     }
-    let turn = hokm.turn();
-    hokm.players[turn.as_index()].hand.cards.retain(|c| *c != card);
-    hokm.trick.as_mut().unwrap().played_cards[turn.as_index()] = Some(card);
-    hokm.turn.increment();
-    GameEvent::PlayedCard(turn, card)
+    let trump_suit = hokm.trump_suit.ok_or(HokmError::WrongPhase(hokm.game_state))?;
+    let completed = hokm.trick.as_ref()
+        .and_then(|t| t.complete(trump_suit))
+        .ok_or(HokmError::WrongPhase(hokm.game_state))?;
+    let winner = completed.winner;
+    hokm.tricks.push(completed);
+    hokm.turn = winner;
+    hokm.trick = Some(Trick::new(winner));
+    Ok(GameEvent::Scored(winner))
 }
@@ -0,0 +1,213 @@
+
+use super::*;
+use crate::cards::*;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use std::mem;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchRules {
+    // number of hand-points needed to win the match
+    pub target: u32,
+    pub hand_points: u32,
+    // a team wins 7-0 (kot)
+    pub kot_points: u32,
+    // the trump caller's team loses 0-7 (hakem kot)
+    pub hakem_kot_points: u32,
+    // whether players may take back cards, usually not in tournaments
+    pub allow_undo: bool,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        MatchRules {
+            target: 7,
+            hand_points: 1,
+            kot_points: 2,
+            hakem_kot_points: 3,
+            allow_undo: true,
+        }
+    }
+}
+
+impl MatchRules {
+    pub fn score_hand(&self, trump_caller: PlayerNumber, tricks: (u32, u32)) -> (Team, Option<Kot>, u32) {
+        let (winner, loser_tricks) = match tricks {
+            (t13, t24) if t13 > t24 => (Team::PlayersOneAndThree, t24),
+            (t13, _) => (Team::PlayersTwoAndFour, t13),
+        };
+        match loser_tricks {
+            0 if trump_caller.team() != winner => (winner, Some(Kot::HakemKot), self.hakem_kot_points),
+            0 => (winner, Some(Kot::Kot), self.kot_points),
+            _ => (winner, None, self.hand_points),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kot {
+    Kot,
+    HakemKot,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandResult {
+    pub trump_caller: PlayerNumber,
+    pub trump_suit: Suit,
+    pub winner: Team,
+    pub tricks: (u32, u32),
+    pub kot: Option<Kot>,
+    pub points: u32,
+}
+
+impl HandResult {
+    // The result of a finished hand
+    pub fn new(rules: &MatchRules, hand: &Hokm) -> Option<HandResult> {
+        hand.determine_winner()?;
+        let trump_caller = hand.trump_caller();
+        let tricks = hand.team_scores();
+        let (winner, kot, points) = rules.score_hand(trump_caller, tricks);
+        Some(HandResult {
+            trump_caller,
+            trump_suit: hand.trump_suit()?,
+            winner,
+            tricks,
+            kot,
+            points,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchEvent {
+    Hand(GameEvent),
+    NewHand(PlayerNumber),
+    Won(Team),
+}
+
+pub struct Match {
+    rules: MatchRules,
+    seed: u64,
+    // seeds the hands of the match
+    rng: StdRng,
+    hand: Hokm,
+    hand_over: bool,
+    scores: (u32, u32),
+    history: Vec<HandResult>,
+}
+
+impl Match {
+    // The first trump caller is chosen by an ace draw
+    pub fn new(rules: MatchRules) -> Self {
+        Match::with_seed(rules, thread_rng().gen())
+    }
+
+    pub fn with_trump_caller<N: Into<PlayerNumber>>(rules: MatchRules, trump_caller: N) -> Self {
+        Match::with_hand(rules, Hokm::new(trump_caller))
+    }
+
+    pub fn with_seed(rules: MatchRules, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut hand = Hokm::with_ace_draw_and_seed(rng.gen());
+        hand.set_undo_allowed(rules.allow_undo);
+        Match {
+            rules,
+            seed,
+            rng,
+            hand,
+            hand_over: false,
+            scores: (0, 0),
+            history: Vec::new(),
+        }
+    }
+
+    // Starts the match with a hand that may already be under way, e.g. a
+    // saved one. The following hands are seeded by the hand's seed.
+    pub fn with_hand(rules: MatchRules, mut hand: Hokm) -> Self {
+        let seed = hand.seed().unwrap_or_else(|| thread_rng().gen());
+        hand.set_undo_allowed(rules.allow_undo);
+        Match {
+            rules,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            hand,
+            hand_over: false,
+            scores: (0, 0),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn play(&mut self, players: [&dyn Player; 4]) -> Result<MatchEvent, HokmError> {
+        if let Some(team) = self.determine_winner() {
+            return Ok(MatchEvent::Won(team));
+        }
+        if self.hand_over {
+            let trump_caller = self.hand.next_trump_caller().expect("hand over w/o winner?!");
+            let observers = mem::take(&mut self.hand.observers);
+            self.hand = Hokm::with_seed(trump_caller, self.rng.gen());
+            self.hand.set_undo_allowed(self.rules.allow_undo);
+            self.hand.observers = observers;
+            self.hand_over = false;
+            return Ok(MatchEvent::NewHand(trump_caller));
+        }
+        let event = self.hand.play(players)?;
+        if let GameEvent::Won(_) = event {
+            self.finish_hand();
+        }
+        Ok(MatchEvent::Hand(event))
+    }
+
+    // The observer is notified of the events of every hand of the match
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.hand.add_observer(observer);
+    }
+
+    // For players whose decision is pending, see `Hokm::set_trump_suit`
+    pub fn set_trump_suit(&mut self, player: PlayerNumber, suit: Suit) -> Result<GameEvent, HokmError> {
+        self.hand.set_trump_suit(player, suit)
+    }
+
+    pub fn play_card(&mut self, player: PlayerNumber, card: Card) -> Result<GameEvent, HokmError> {
+        self.hand.play_card(player, card)
+    }
+
+    // Cards can only be taken back until the hand is scored
+    pub fn undo_card(&mut self) -> Result<GameEvent, HokmError> {
+        if self.hand_over {
+            return Err(HokmError::GameFinished);
+        }
+        self.hand.undo_card()
+    }
+
+    pub fn undo_trick(&mut self) -> Result<Vec<GameEvent>, HokmError> {
+        if self.hand_over {
+            return Err(HokmError::GameFinished);
+        }
+        self.hand.undo_trick()
+    }
+
+    fn finish_hand(&mut self) {
+        let result = HandResult::new(&self.rules, &self.hand).expect("hand finished w/o winner?!");
+        match result.winner {
+            Team::PlayersOneAndThree => self.scores.0 += result.points,
+            Team::PlayersTwoAndFour => self.scores.1 += result.points,
+        }
+        self.history.push(result);
+        self.hand_over = true;
+    }
+
+    pub fn determine_winner(&self) -> Option<Team> {
+        match self.scores {
+            (t13, _) if t13 >= self.rules.target => Some(Team::PlayersOneAndThree),
+            (_, t24) if t24 >= self.rules.target => Some(Team::PlayersTwoAndFour),
+            _ => None
+        }
+    }
+
+    pub fn rules(&self) -> &MatchRules       { &self.rules }
+    pub fn seed(&self) -> u64                { self.seed }
+    pub fn hand(&self) -> &Hokm              { &self.hand }
+    pub fn scores(&self) -> (u32, u32)       { self.scores }
+    pub fn history(&self) -> &[HandResult]   { &self.history }
+    pub fn last_hand(&self) -> Option<&HandResult> { self.history.last() }
+}
@@ -1,8 +1,26 @@
 
+mod deal;
+mod error;
 mod trick;
 mod hokm;
 mod player;
+mod match_play;
+mod observer;
+mod record;
+mod save;
+mod table;
+mod view;
+#[cfg(test)]
+mod tests;
 
+pub use self::deal::*;
+pub use self::error::*;
 pub use self::trick::*;
 pub use self::hokm::*;
 pub use self::player::*;
+pub use self::match_play::*;
+pub use self::observer::*;
+pub use self::record::*;
+pub use self::save::*;
+pub use self::table::*;
+pub use self::view::*;
@@ -0,0 +1,24 @@
+
+use super::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Receives every event of a game it is registered on, after the event has
+// happened, see `Hokm::add_observer`. `Waiting` is not an event that happens.
+pub trait GameObserver {
+    fn on_event(&mut self, hokm: &Hokm, event: GameEvent);
+}
+
+// Keep a handle to the observer to read from it later, e.g.
+// `hokm.add_observer(Box::new(record.clone()))` for an `Rc<RefCell<GameRecord>>`
+impl<T: GameObserver> GameObserver for Rc<RefCell<T>> {
+    fn on_event(&mut self, hokm: &Hokm, event: GameEvent) {
+        self.borrow_mut().on_event(hokm, event);
+    }
+}
+
+impl GameObserver for GameRecord {
+    fn on_event(&mut self, hokm: &Hokm, event: GameEvent) {
+        self.record(hokm, event);
+    }
+}
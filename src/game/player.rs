@@ -2,12 +2,45 @@
 use super::*;
 use crate::cards::*;
 
+// Players decide from what they can see of the game, see `PlayerView`.
+// Played cards and completed tricks are part of the view.
 pub trait Player {
     fn name(&self) -> String;
-    fn call_trump_suit(&self, hand: &Hand) -> Suit;
-    fn play(&self, hand: &Hand, trump_suit: Suit, trick: &Trick) -> Card;
-    fn trick_end(&self, _trick: &Trick) {
-        // ... so that player can keep track of played cards
+    // Called with the first five cards of the hakem
+    fn call_trump_suit(&self, view: &PlayerView) -> Decision<Suit>;
+    // The card must be one of `view.legal_moves()`
+    fn play(&self, view: &PlayerView) -> Decision<Card>;
+}
+
+// A player that has not decided yet (e.g. a human or a remote player)
+// returns `Pending`, and is asked again on the next `Hokm::play`. The
+// decision can also be passed to `Hokm::set_trump_suit` or
+// `Hokm::play_card` directly once it is made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision<T> {
+    Ready(T),
+    Pending,
+}
+
+impl<T> Decision<T> {
+    pub fn is_pending(&self) -> bool {
+        match self {
+            Decision::Ready(_) => false,
+            Decision::Pending => true,
+        }
+    }
+
+    pub fn ready(self) -> Option<T> {
+        match self {
+            Decision::Ready(x) => Some(x),
+            Decision::Pending => None,
+        }
+    }
+}
+
+impl<T> From<T> for Decision<T> {
+    fn from(x: T) -> Self {
+        Decision::Ready(x)
     }
 }
 
@@ -35,6 +68,13 @@ impl PlayerNumber {
     pub fn from_index(idx: usize) -> Self {
         (idx + 1).into()
     }
+
+    pub fn team(&self) -> Team {
+        match self {
+            PlayerNumber::One | PlayerNumber::Three => Team::PlayersOneAndThree,
+            PlayerNumber::Two | PlayerNumber::Four  => Team::PlayersTwoAndFour,
+        }
+    }
 }
 
 impl From<usize> for PlayerNumber {
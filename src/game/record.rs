@@ -0,0 +1,281 @@
+
+use super::*;
+use super::save::{parse_cards, parse_player};
+use crate::cards::*;
+use std::io::{self, BufRead, Write};
+
+// Game records are lines of text, e.g.
+//
+//     hokm-record 1
+//     seed 1234
+//     hakem 2
+//     hand 1 2S 5S 9S ...
+//     ...
+//     trump H
+//     trick 2: AS KS 2S 5S -> 2
+//     trick 2: KH 4H JH 6H -> 2
+//     ...
+//     result 2-7
+//
+// Hands list the cards in the order they are dealt, trick lines start with
+// the leader and list the cards in the order they are played.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrickRecord {
+    pub leader: PlayerNumber,
+    pub cards: Vec<Card>,
+    pub winner: Option<PlayerNumber>,
+}
+
+impl TrickRecord {
+    pub fn plays(&self) -> impl Iterator<Item = (PlayerNumber, Card)> + '_ {
+        let mut p = self.leader;
+        self.cards.iter().map(move |&card| {
+            let player = p;
+            p.increment();
+            (player, card)
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GameRecord {
+    pub seed: Option<u64>,
+    // not known before the trump caller is chosen
+    pub deal: Option<Deal>,
+    pub trump_suit: Option<Suit>,
+    pub tricks: Vec<TrickRecord>,
+    // tricks won by each team
+    pub result: Option<(u32, u32)>,
+}
+
+impl GameRecord {
+    pub fn new(hokm: &Hokm) -> Self {
+        GameRecord {
+            seed: hokm.seed(),
+            deal: hokm.deal().cloned(),
+            trump_suit: hokm.trump_suit(),
+            ..GameRecord::default()
+        }
+    }
+
+    // Call with every event of the game, in order
+    pub fn record(&mut self, hokm: &Hokm, event: GameEvent) {
+        match event {
+            GameEvent::ChoseTrumpCaller(_) => self.deal = hokm.deal().cloned(),
+            GameEvent::SetTrumpSuit(suit) => self.trump_suit = Some(suit),
+            GameEvent::PlayedCard(p, card) => {
+                match self.tricks.last_mut() {
+                    Some(trick) if trick.cards.len() < 4 => trick.cards.push(card),
+                    _ => self.tricks.push(TrickRecord { leader: p, cards: vec![card], winner: None }),
+                }
+            }
+            GameEvent::Scored(p) => {
+                if let Some(trick) = self.tricks.last_mut() {
+                    trick.winner = Some(p);
+                }
+            }
+            GameEvent::TookBack(..) => {
+                self.result = None;
+                if let Some(trick) = self.tricks.last_mut() {
+                    trick.winner = None;
+                    trick.cards.pop();
+                    if trick.cards.is_empty() {
+                        self.tricks.pop();
+                    }
+                }
+            }
+            GameEvent::Won(_) => self.result = Some(hokm.team_scores()),
+            _ => {}
+        }
+    }
+
+    pub fn plays(&self) -> impl Iterator<Item = (PlayerNumber, Card)> + '_ {
+        self.tricks.iter().flat_map(|t| t.plays())
+    }
+
+    pub fn winner(&self) -> Option<Team> {
+        match self.result? {
+            (t13, t24) if t13 > t24 => Some(Team::PlayersOneAndThree),
+            _ => Some(Team::PlayersTwoAndFour),
+        }
+    }
+
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "hokm-record {}", FORMAT_VERSION)?;
+        if let Some(seed) = self.seed {
+            writeln!(w, "seed {}", seed)?;
+        }
+        if let Some(ref deal) = self.deal {
+            writeln!(w, "hakem {}", deal.trump_caller() as usize)?;
+            for i in 1..=4 {
+                write!(w, "hand {}", i)?;
+                for card in deal.hand(i) {
+                    write!(w, " {}", card)?;
+                }
+                writeln!(w)?;
+            }
+        }
+        if let Some(suit) = self.trump_suit {
+            writeln!(w, "trump {}", suit)?;
+        }
+        for trick in self.tricks.iter() {
+            write!(w, "trick {}:", trick.leader as usize)?;
+            for card in trick.cards.iter() {
+                write!(w, " {}", card)?;
+            }
+            if let Some(winner) = trick.winner {
+                write!(w, " -> {}", winner as usize)?;
+            }
+            writeln!(w)?;
+        }
+        if let Some((t13, t24)) = self.result {
+            writeln!(w, "result {}-{}", t13, t24)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(r: R) -> Result<GameRecord, LoadError> {
+        let mut record = GameRecord::default();
+        let mut lines = r.lines().enumerate();
+        match lines.next() {
+            Some((_, line)) => {
+                let line = line?;
+                match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    ["hokm-record", v] if v == FORMAT_VERSION.to_string() => {},
+                    ["hokm-record", v] => return Err(LoadError::UnsupportedVersion(v.to_owned())),
+                    _ => return Err(LoadError::Invalid(1, "not a hokm game record".to_owned())),
+                }
+            }
+            None => return Err(LoadError::Invalid(1, "empty file".to_owned())),
+        }
+        let mut hakem = None;
+        let mut hands = [Hand::new(), Hand::new(), Hand::new(), Hand::new()];
+        for (i, line) in lines {
+            let line = line?;
+            let invalid = |msg: &str| LoadError::Invalid(i + 1, format!("{}: {}", msg, line));
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                [] => {},
+                ["seed", seed] => record.seed = Some(seed.parse().map_err(|_| invalid("invalid seed"))?),
+                ["hakem", p] => hakem = Some(parse_player(p).ok_or_else(|| invalid("invalid player"))?),
+                ["hand", p, ref cards @ ..] => {
+                    let p = parse_player(p).ok_or_else(|| invalid("invalid player"))?;
+                    hands[p.as_index()] = Hand::from_cards(parse_cards(cards).ok_or_else(|| invalid("invalid card"))?);
+                }
+                ["trump", suit] => record.trump_suit = Some(suit.parse().map_err(|_| invalid("invalid suit"))?),
+                ["trick", leader, ref rest @ ..] => {
+                    let leader = leader.strip_suffix(':')
+                        .and_then(parse_player)
+                        .ok_or_else(|| invalid("invalid leader"))?;
+                    let (cards, winner) = match rest {
+                        [cards @ .., "->", winner] => (cards, Some(parse_player(winner).ok_or_else(|| invalid("invalid winner"))?)),
+                        cards => (cards, None),
+                    };
+                    let cards = parse_cards(cards).ok_or_else(|| invalid("invalid card"))?;
+                    if cards.is_empty() || cards.len() > 4 || (winner.is_some() && cards.len() < 4) {
+                        return Err(invalid("invalid trick"));
+                    }
+                    record.tricks.push(TrickRecord { leader, cards, winner });
+                }
+                ["result", result] => {
+                    let mut scores = result.splitn(2, '-').map(|s| s.parse().ok());
+                    match (scores.next().flatten(), scores.next().flatten()) {
+                        (Some(t13), Some(t24)) => record.result = Some((t13, t24)),
+                        _ => return Err(invalid("invalid result")),
+                    }
+                }
+                _ => return Err(invalid("unknown line")),
+            }
+        }
+        if let Some(hakem) = hakem {
+            let [h1, h2, h3, h4] = hands;
+            let mut first_five = [Card::from_index(0).unwrap(); 5];
+            let caller_hand = [&h1, &h2, &h3, &h4][hakem.as_index()].cards();
+            if caller_hand.len() < 5 {
+                return Err(LoadError::Inconsistent("the hakem has less than five cards".to_owned()));
+            }
+            first_five.copy_from_slice(&caller_hand[..5]);
+            let deal = Deal::new(hakem, [h1, h2, h3, h4], first_five)
+                .map_err(|e| LoadError::Inconsistent(e.to_string()))?;
+            record.deal = Some(deal);
+        }
+        Ok(record)
+    }
+
+    // Replays the game from the start, see `Replay`
+    pub fn replay(&self) -> Result<Replay, HokmError> {
+        Replay::new(self.clone())
+    }
+}
+
+// Steps through a recorded game. Each step is one step of the engine, i.e.
+// either a played card or the collection of a trick.
+pub struct Replay {
+    record: GameRecord,
+    hokm: Hokm,
+    plays: Vec<(PlayerNumber, Card)>,
+    // number of steps taken from the start
+    position: usize,
+    // number of cards played so far
+    played: usize,
+}
+
+impl Replay {
+    // The game starts right after the trump suit is called and the hands
+    // are dealt and sorted.
+    pub fn new(record: GameRecord) -> Result<Replay, HokmError> {
+        let deal = record.deal.clone().ok_or(HokmError::NoDeal)?;
+        let mut hokm = Hokm::from_deal(deal);
+        while hokm.waiting_for().is_none() {
+            hokm.advance()?;
+        }
+        if let Some(suit) = record.trump_suit {
+            hokm.set_trump_suit(hokm.trump_caller(), suit)?;
+            while hokm.waiting_for().is_none() {
+                hokm.advance()?;
+            }
+        }
+        let plays = record.plays().collect();
+        Ok(Replay { record, hokm, plays, position: 0, played: 0 })
+    }
+
+    pub fn record(&self) -> &GameRecord { &self.record }
+    pub fn hokm(&self) -> &Hokm         { &self.hokm }
+    pub fn position(&self) -> usize     { self.position }
+
+    // Returns None at the end of the record
+    pub fn forward(&mut self) -> Option<Result<GameEvent, HokmError>> {
+        let event = match self.hokm.waiting_for() {
+            Some(_) => {
+                let (p, card) = *self.plays.get(self.played)?;
+                self.played += 1;
+                self.hokm.play_card(p, card)
+            }
+            None => match self.hokm.advance() {
+                Err(HokmError::GameFinished) => return None,
+                event => event,
+            },
+        };
+        self.position += 1;
+        Some(event)
+    }
+
+    pub fn backward(&mut self) -> Result<(), HokmError> {
+        self.seek(self.position.saturating_sub(1))
+    }
+
+    // Replays the game from the start up to the given step
+    pub fn seek(&mut self, position: usize) -> Result<(), HokmError> {
+        let mut replay = Replay::new(self.record.clone())?;
+        while replay.position < position {
+            match replay.forward() {
+                Some(event) => { event?; },
+                None => break,
+            }
+        }
+        *self = replay;
+        Ok(())
+    }
+}
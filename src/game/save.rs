@@ -0,0 +1,328 @@
+
+use super::*;
+use crate::cards::*;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+// Games are saved as lines of text, each line a key followed by its values.
+// Cards use the ASCII notation and card lists keep their order, e.g.
+//
+//     hokm 2
+//     seed 1234
+//     state NormalPlay
+//     trump_caller 2
+//     turn 3
+//     trump_suit H
+//     hand 1 2S 5S 9S ...
+//     completed 1 AS 2S 7S 3S 1
+//     trick 3 - - 5H -
+//
+// Completed tricks list the leader, the cards in play order and the winner.
+// The current trick lists the leader and the cards by player, `-` for
+// players who have not played yet. `undo off` is written for games that do
+// not allow taking back cards.
+//
+// Version 1 files are read too: they list completed tricks like the current
+// one, as `collected`, and the tricks won by each player, as `score`, which
+// follow from the tricks.
+const FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    UnsupportedVersion(String),
+    // line number and description of the problem
+    Invalid(usize, String),
+    Inconsistent(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::UnsupportedVersion(v) => write!(f, "unsupported save format version: {}", v),
+            LoadError::Invalid(line, msg) => write!(f, "line {}: {}", line, msg),
+            LoadError::Inconsistent(msg) => write!(f, "inconsistent game: {}", msg),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+fn write_cards<W: Write>(w: &mut W, key: &str, cards: &[Card]) -> io::Result<()> {
+    write!(w, "{}", key)?;
+    for card in cards {
+        write!(w, " {}", card)?;
+    }
+    writeln!(w)
+}
+
+impl Hokm {
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "hokm {}", FORMAT_VERSION)?;
+        if let Some(seed) = self.seed {
+            writeln!(w, "seed {}", seed)?;
+        }
+        writeln!(w, "state {:?}", self.game_state)?;
+        writeln!(w, "trump_caller {}", self.trump_caller as usize)?;
+        writeln!(w, "turn {}", self.turn as usize)?;
+        if let Some(suit) = self.trump_suit {
+            writeln!(w, "trump_suit {}", suit)?;
+        }
+        write_cards(&mut w, "deck", self.deck.cards())?;
+        if let Some(ref deck) = self.next_deck {
+            write_cards(&mut w, "next_deck", deck.cards())?;
+        }
+        if let Some(ref deal) = self.deal {
+            let key = format!("deal {}", deal.trump_caller() as usize);
+            write_cards(&mut w, &key, deal.to_deck().cards())?;
+        }
+        for &(p, card) in self.face_up_cards.iter() {
+            writeln!(w, "face_up {} {}", p as usize, card)?;
+        }
+        for (i, ps) in self.players.iter().enumerate() {
+            write_cards(&mut w, &format!("hand {}", i + 1), ps.hand.cards())?;
+        }
+        for trick in self.tricks.iter() {
+            write!(w, "completed {}", trick.leader as usize)?;
+            for card in trick.cards.iter() {
+                write!(w, " {}", card)?;
+            }
+            writeln!(w, " {}", trick.winner as usize)?;
+        }
+        if let Some(ref trick) = self.trick {
+            write!(w, "trick {}", trick.first_player as usize)?;
+            for card in trick.played_cards.iter() {
+                match card {
+                    Some(card) => write!(w, " {}", card)?,
+                    None => write!(w, " -")?,
+                }
+            }
+            writeln!(w)?;
+        }
+        if !self.allow_undo {
+            writeln!(w, "undo off")?;
+        }
+        Ok(())
+    }
+
+    pub fn load<R: BufRead>(r: R) -> Result<Hokm, LoadError> {
+        let mut hokm = Hokm {
+            seed: None,
+            deck: Deck::from_cards(Vec::new()),
+            next_deck: None,
+            deal: None,
+            players: [
+                PlayerState::new(),
+                PlayerState::new(),
+                PlayerState::new(),
+                PlayerState::new(),
+            ],
+            trump_suit: None,
+            trump_caller: PlayerNumber::One,
+            turn: PlayerNumber::One,
+            trick: None,
+            tricks: Vec::new(),
+            allow_undo: true,
+            observers: Vec::new(),
+            face_up_cards: Vec::new(),
+            game_state: GameState::DealingInitialFiveCards,
+        };
+        let mut lines = r.lines().enumerate();
+        let version = match lines.next() {
+            Some((_, line)) => {
+                let line = line?;
+                match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    ["hokm", v] => match v.parse() {
+                        Ok(version) if (1..=FORMAT_VERSION).contains(&version) => version,
+                        _ => return Err(LoadError::UnsupportedVersion(v.to_owned())),
+                    },
+                    _ => return Err(LoadError::Invalid(1, "not a saved hokm game".to_owned())),
+                }
+            }
+            None => return Err(LoadError::Invalid(1, "empty file".to_owned())),
+        };
+        // the completed tricks of version 1, which need the trump suit
+        let mut collected = Vec::new();
+        let mut seen = [false, false, false];
+        for (i, line) in lines {
+            let line = line?;
+            let invalid = |msg: &str| LoadError::Invalid(i + 1, format!("{}: {}", msg, line));
+            let mut words = line.split_whitespace();
+            let key = match words.next() {
+                Some(key) => key,
+                None => continue,
+            };
+            let values: Vec<&str> = words.collect();
+            let value = |j: usize| values.get(j).cloned().ok_or_else(|| invalid("missing value"));
+            match key {
+                "seed" => hokm.seed = Some(value(0)?.parse().map_err(|_| invalid("invalid seed"))?),
+                "state" => {
+                    hokm.game_state = parse_game_state(value(0)?).ok_or_else(|| invalid("invalid state"))?;
+                    seen[0] = true;
+                }
+                "trump_caller" => {
+                    hokm.trump_caller = parse_player(value(0)?).ok_or_else(|| invalid("invalid player"))?;
+                    seen[1] = true;
+                }
+                "turn" => {
+                    hokm.turn = parse_player(value(0)?).ok_or_else(|| invalid("invalid player"))?;
+                    seen[2] = true;
+                }
+                "trump_suit" => hokm.trump_suit = Some(value(0)?.parse().map_err(|_| invalid("invalid suit"))?),
+                "deck" => hokm.deck = Deck::from_cards(parse_cards(&values).ok_or_else(|| invalid("invalid card"))?),
+                "next_deck" => hokm.next_deck = Some(Deck::from_cards(parse_cards(&values).ok_or_else(|| invalid("invalid card"))?)),
+                "deal" => {
+                    let p = parse_player(value(0)?).ok_or_else(|| invalid("invalid player"))?;
+                    let cards = parse_cards(&values[1..]).ok_or_else(|| invalid("invalid card"))?;
+                    if !is_partition(cards.iter()) {
+                        return Err(invalid("not a full deck"));
+                    }
+                    hokm.deal = Some(Deal::from_deck(p, &Deck::from_cards(cards)));
+                }
+                "face_up" => {
+                    let p = parse_player(value(0)?).ok_or_else(|| invalid("invalid player"))?;
+                    let card = value(1)?.parse().map_err(|_| invalid("invalid card"))?;
+                    hokm.face_up_cards.push((p, card));
+                }
+                "hand" => {
+                    let p = parse_player(value(0)?).ok_or_else(|| invalid("invalid player"))?;
+                    let cards = parse_cards(&values[1..]).ok_or_else(|| invalid("invalid card"))?;
+                    hokm.players[p.as_index()].hand = Hand::from_cards(cards);
+                }
+                "completed" => {
+                    if values.len() > 6 {
+                        return Err(invalid("too many values"));
+                    }
+                    let leader = parse_player(value(0)?).ok_or_else(|| invalid("invalid player"))?;
+                    let winner = parse_player(value(5)?).ok_or_else(|| invalid("invalid player"))?;
+                    let mut cards = [Card::from_index(0).unwrap(); 4];
+                    for (j, card) in cards.iter_mut().enumerate() {
+                        *card = value(j + 1)?.parse().map_err(|_| invalid("invalid card"))?;
+                    }
+                    hokm.tricks.push(CompletedTrick { leader, cards, winner });
+                }
+                "trick" => hokm.trick = Some(parse_trick(&values).ok_or_else(|| invalid("invalid trick"))?),
+                "collected" if version == 1 => {
+                    match parse_trick(&values) {
+                        Some(trick) if trick.have_all_played() => collected.push(trick),
+                        _ => return Err(invalid("invalid trick")),
+                    }
+                }
+                "score" if version == 1 => {},
+                "undo" => hokm.allow_undo = match value(0)? {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(invalid("invalid value")),
+                },
+                _ => return Err(invalid("unknown key")),
+            }
+        }
+        if seen.iter().any(|s| !s) {
+            return Err(LoadError::Inconsistent("missing state, trump_caller or turn".to_owned()));
+        }
+        for trick in collected {
+            let completed = hokm.trump_suit.and_then(|suit| trick.complete(suit));
+            hokm.tricks.push(completed.ok_or_else(|| LoadError::Inconsistent("tricks w/o trump suit".to_owned()))?);
+        }
+        for trick in hokm.tricks.iter() {
+            if hokm.trump_suit.and_then(|suit| trick.to_trick().winner(suit)) != Some(trick.winner) {
+                return Err(LoadError::Inconsistent(format!("wrong winner of a trick led by {}", trick.leader as usize)));
+            }
+        }
+        let trick_cards = hokm.trick.iter().flat_map(|t| t.played_cards.iter().flatten());
+        let completed_cards = hokm.tricks.iter().flat_map(|t| t.cards.iter());
+        let hand_cards = hokm.players.iter().flat_map(|p| p.hand.cards().iter());
+        // the cards drawn for the trump caller come from the deck, until the
+        // next deck takes its place
+        let face_up = match hokm.game_state {
+            GameState::DrawingForTrumpCaller => &hokm.face_up_cards[..],
+            _ => &[],
+        };
+        let face_up_cards = face_up.iter().map(|(_, card)| card);
+        let mut dealt = [false; 52];
+        for card in hokm.deck.cards().iter().chain(hand_cards).chain(trick_cards).chain(completed_cards).chain(face_up_cards) {
+            if dealt[card.index()] {
+                return Err(LoadError::Inconsistent(format!("duplicate card {}", card)));
+            }
+            dealt[card.index()] = true;
+        }
+        if let Some(i) = dealt.iter().position(|d| !d) {
+            return Err(LoadError::Inconsistent(format!("missing card {}", Card::from_index(i).unwrap())));
+        }
+        // once the cards are dealt, each player has played a card to every
+        // completed trick and maybe one to the current trick
+        let played = hokm.tricks.len();
+        match hokm.game_state {
+            GameState::SortHands | GameState::NormalPlay | GameState::Finished => {
+                for (i, ps) in hokm.players.iter().enumerate() {
+                    let in_trick = hokm.trick.as_ref().map_or(false, |t| t.played_cards[i].is_some());
+                    if ps.hand.len() + played + in_trick as usize != 13 {
+                        return Err(LoadError::Inconsistent(format!("player {} holds {} cards after {} tricks", i + 1, ps.hand.len(), played)));
+                    }
+                }
+            }
+            _ if played > 0 => return Err(LoadError::Inconsistent("tricks played while dealing".to_owned())),
+            _ => {},
+        }
+        Ok(hokm)
+    }
+}
+
+pub(super) fn parse_player(s: &str) -> Option<PlayerNumber> {
+    match s.parse::<usize>() {
+        Ok(x) if (1..=4).contains(&x) => Some(PlayerNumber::from(x)),
+        _ => None,
+    }
+}
+
+// The leader and the cards by player, `-` for players who have not played
+fn parse_trick(values: &[&str]) -> Option<Trick> {
+    if values.len() != 5 {
+        return None;
+    }
+    let mut trick = Trick::new(parse_player(values[0])?);
+    for (played, value) in trick.played_cards.iter_mut().zip(values[1..].iter()) {
+        *played = match *value {
+            "-" => None,
+            card => Some(card.parse().ok()?),
+        };
+    }
+    Some(trick)
+}
+
+pub(super) fn parse_cards(values: &[&str]) -> Option<Vec<Card>> {
+    values.iter().map(|s| s.parse().ok()).collect()
+}
+
+fn is_partition<'a, I: Iterator<Item = &'a Card>>(cards: I) -> bool {
+    let mut dealt = [false; 52];
+    for card in cards {
+        if dealt[card.index()] {
+            return false;
+        }
+        dealt[card.index()] = true;
+    }
+    dealt.iter().all(|d| *d)
+}
+
+fn parse_game_state(s: &str) -> Option<GameState> {
+    use GameState::*;
+    match s {
+        "DrawingForTrumpCaller" => Some(DrawingForTrumpCaller),
+        "DealingInitialFiveCards" => Some(DealingInitialFiveCards),
+        "SettingTrumpSuit" => Some(SettingTrumpSuit),
+        "DealingRestOfCards" => Some(DealingRestOfCards),
+        "SortHands" => Some(SortHands),
+        "NormalPlay" => Some(NormalPlay),
+        "Finished" => Some(Finished),
+        _ => None,
+    }
+}
@@ -0,0 +1,108 @@
+
+use super::*;
+
+// Four players sitting at a table, playing a single hand or a whole match
+pub struct Table {
+    players: [Box<dyn Player>; 4],
+    game: TableGame,
+}
+
+enum TableGame {
+    Hand(Box<Hokm>),
+    Match(Box<Match>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchResult {
+    pub winner: Team,
+    pub scores: (u32, u32),
+    pub hands: Vec<HandResult>,
+}
+
+impl Table {
+    pub fn new(players: [Box<dyn Player>; 4], hand: Hokm) -> Self {
+        Table { players, game: TableGame::Hand(Box::new(hand)) }
+    }
+
+    pub fn with_match(players: [Box<dyn Player>; 4], m: Match) -> Self {
+        Table { players, game: TableGame::Match(Box::new(m)) }
+    }
+
+    // Events of a single hand are wrapped in `MatchEvent::Hand`
+    pub fn step(&mut self) -> Result<MatchEvent, HokmError> {
+        let players = [
+            self.players[0].as_ref(),
+            self.players[1].as_ref(),
+            self.players[2].as_ref(),
+            self.players[3].as_ref(),
+        ];
+        match self.game {
+            TableGame::Hand(ref mut hand) => hand.play(players).map(MatchEvent::Hand),
+            TableGame::Match(ref mut m) => m.play(players),
+        }
+    }
+
+    // Plays until the current hand is won, or the next hand of a match if
+    // the current one is over. Hands that are not part of a match are
+    // scored with the default rules.
+    pub fn run_hand(&mut self) -> Result<HandResult, HokmError> {
+        loop {
+            match self.step()? {
+                MatchEvent::Hand(GameEvent::Won(_)) => break,
+                MatchEvent::Won(_) => return Err(HokmError::GameFinished),
+                _ => {},
+            }
+        }
+        let result = match self.game {
+            TableGame::Hand(ref hand) => HandResult::new(&MatchRules::default(), hand),
+            TableGame::Match(ref m) => m.last_hand().copied(),
+        };
+        Ok(result.expect("hand won w/o result?!"))
+    }
+
+    // Plays until the match is won. A single hand is played as a match of
+    // one hand.
+    pub fn run_match(&mut self) -> Result<MatchResult, HokmError> {
+        if let TableGame::Hand(_) = self.game {
+            let hand = self.run_hand()?;
+            let scores = match hand.winner {
+                Team::PlayersOneAndThree => (hand.points, 0),
+                Team::PlayersTwoAndFour => (0, hand.points),
+            };
+            return Ok(MatchResult { winner: hand.winner, scores, hands: vec![hand] });
+        }
+        let winner = loop {
+            if let MatchEvent::Won(team) = self.step()? {
+                break team;
+            }
+        };
+        let m = self.game_match().expect("a match");
+        Ok(MatchResult { winner, scores: m.scores(), hands: m.history().to_vec() })
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        match self.game {
+            TableGame::Hand(ref mut hand) => hand.add_observer(observer),
+            TableGame::Match(ref mut m) => m.add_observer(observer),
+        }
+    }
+
+    // The hand being played
+    pub fn hokm(&self) -> &Hokm {
+        match self.game {
+            TableGame::Hand(ref hand) => hand,
+            TableGame::Match(ref m) => m.hand(),
+        }
+    }
+
+    pub fn game_match(&self) -> Option<&Match> {
+        match self.game {
+            TableGame::Hand(_) => None,
+            TableGame::Match(ref m) => Some(m),
+        }
+    }
+
+    pub fn player(&self, p: PlayerNumber) -> &dyn Player {
+        self.players[p.as_index()].as_ref()
+    }
+}
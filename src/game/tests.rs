@@ -0,0 +1,699 @@
+
+use crate::cards::*;
+use crate::game::*;
+use crate::players::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+#[test]
+fn hand_scoring() {
+    let rules = MatchRules::default();
+    use PlayerNumber::*;
+    assert_eq!(rules.score_hand(One, (7, 3)), (Team::PlayersOneAndThree, None, 1));
+    assert_eq!(rules.score_hand(One, (5, 7)), (Team::PlayersTwoAndFour, None, 1));
+    assert_eq!(rules.score_hand(Two, (7, 0)), (Team::PlayersOneAndThree, Some(Kot::HakemKot), 3));
+    assert_eq!(rules.score_hand(Three, (7, 0)), (Team::PlayersOneAndThree, Some(Kot::Kot), 2));
+    assert_eq!(rules.score_hand(Three, (0, 7)), (Team::PlayersTwoAndFour, Some(Kot::HakemKot), 3));
+}
+
+#[test]
+fn full_match() {
+    let rules = MatchRules::default();
+    let mut table = Table::with_match([
+        Box::new(SensiblePlayer::new()),
+        Box::new(SensiblePlayer::new()),
+        Box::new(SensiblePlayer::new()),
+        Box::new(SensiblePlayer::new()),
+    ], Match::with_seed(rules, 7));
+    let result = table.run_match().unwrap();
+    let m = table.game_match().unwrap();
+    assert_eq!(m.determine_winner(), Some(result.winner));
+    assert_eq!(m.history(), &result.hands[..]);
+    let (s13, s24) = m.scores();
+    assert!(s13 >= rules.target || s24 >= rules.target);
+    let points = |team| m.history().iter()
+        .filter(|h| h.winner == team)
+        .map(|h| h.points)
+        .sum::<u32>();
+    assert_eq!(points(Team::PlayersOneAndThree), s13);
+    assert_eq!(points(Team::PlayersTwoAndFour), s24);
+    for h in m.history() {
+        assert!(h.tricks.0 == 7 || h.tricks.1 == 7);
+        assert_eq!(h.kot.is_some(), h.tricks.0 == 0 || h.tricks.1 == 0);
+    }
+    for h in m.history().windows(2) {
+        let mut next = h[0].trump_caller;
+        if h[0].winner != next.team() {
+            next.increment();
+        }
+        assert_eq!(h[1].trump_caller, next);
+    }
+    assert_eq!(table.run_hand(), Err(HokmError::GameFinished));
+}
+
+#[test]
+fn single_hand_table() {
+    let mut table = Table::new([
+        Box::new(SensiblePlayer::new()),
+        Box::new(RandomPlayer::with_seed(1)),
+        Box::new(SensiblePlayer::new()),
+        Box::new(RandomPlayer::with_seed(2)),
+    ], Hokm::with_seed(PlayerNumber::Four, 12));
+    let result = table.run_hand().unwrap();
+    assert_eq!(result.trump_caller, PlayerNumber::Four);
+    assert_eq!(result.tricks, table.hokm().team_scores());
+    assert_eq!(Some(result.winner), table.hokm().determine_winner());
+    assert_eq!(table.player(PlayerNumber::Two).name(), "Random");
+    assert_eq!(table.step(), Err(HokmError::GameFinished));
+}
+
+#[test]
+fn ace_draw() {
+    for seed in 0..20 {
+        let mut g = Hokm::with_ace_draw_and_seed(seed);
+        let p: &dyn Player = &RandomPlayer::with_seed(seed);
+        let mut drawn = Vec::new();
+        let caller = loop {
+            match g.play([p; 4]).unwrap() {
+                GameEvent::DrewCard(p, c) => drawn.push((p, c)),
+                GameEvent::ChoseTrumpCaller(p) => break p,
+                e => panic!("unexpected event {:?}", e),
+            }
+        };
+        let (last_player, last_card) = *drawn.last().unwrap();
+        assert_eq!(last_player, caller);
+        assert_eq!(last_card.rank(), Rank::Ace);
+        assert!(drawn[..drawn.len() - 1].iter().all(|(_, c)| c.rank() != Rank::Ace));
+        for (i, (p, _)) in drawn.iter().enumerate() {
+            assert_eq!(*p, PlayerNumber::from_index(i % 4));
+        }
+        assert_eq!(g.trump_caller(), caller);
+        assert_eq!(g.deck_size(), 52);
+        assert_eq!(g.game_state(), GameState::DealingInitialFiveCards);
+    }
+}
+
+fn advance_to_decision(g: &mut Hokm) -> PlayerNumber {
+    loop {
+        if let Some(p) = g.waiting_for() {
+            return p;
+        }
+        g.advance().unwrap();
+    }
+}
+
+#[test]
+fn illegal_moves_are_rejected() {
+    use PlayerNumber::*;
+    let mut g = Hokm::with_seed(Two, 1);
+    let some_card = Card::new(Rank::Two, Suit::Hearts);
+    assert_eq!(g.play_card(Two, some_card), Err(HokmError::WrongPhase(GameState::DealingInitialFiveCards)));
+
+    assert_eq!(advance_to_decision(&mut g), Two);
+    assert_eq!(g.advance(), Err(HokmError::WrongPhase(GameState::SettingTrumpSuit)));
+    assert_eq!(g.set_trump_suit(One, Suit::Spades), Err(HokmError::NotPlayersTurn(One)));
+    assert_eq!(g.set_trump_suit(Two, Suit::Spades), Ok(GameEvent::SetTrumpSuit(Suit::Spades)));
+
+    assert_eq!(advance_to_decision(&mut g), Two);
+    let lead = g.player_state(Two).hand().cards()[0];
+    let not_in_hand = g.player_state(One).hand().cards()[0];
+    assert_eq!(g.play_card(Three, lead), Err(HokmError::NotPlayersTurn(Three)));
+    assert_eq!(g.play_card(Two, not_in_hand), Err(HokmError::CardNotInHand(Two, not_in_hand)));
+    assert_eq!(g.play_card(Two, lead), Ok(GameEvent::PlayedCard(Two, lead)));
+
+    let hand = g.player_state(Three).hand().clone();
+    assert!(hand.count_of_suit(lead.suit()) > 0);
+    let card = *hand.cards().iter().find(|c| c.suit() != lead.suit()).unwrap();
+    assert_eq!(g.play_card(Three, card), Err(HokmError::MustFollowSuit(Three, card, lead.suit())));
+    // rejected moves leave the game unchanged
+    assert_eq!(g.turn(), Three);
+    assert_eq!(g.player_state(Three).hand().len(), 13);
+}
+
+#[test]
+fn finished_game() {
+    let mut g = Hokm::with_seed(PlayerNumber::One, 3);
+    let p: &dyn Player = &SensiblePlayer::new();
+    let team = loop {
+        if let GameEvent::Won(team) = g.play([p; 4]).unwrap() {
+            break team;
+        }
+    };
+    assert_eq!(g.game_state(), GameState::Finished);
+    assert_eq!(g.determine_winner(), Some(team));
+    assert_eq!(g.advance(), Err(HokmError::GameFinished));
+    assert_eq!(g.play([p; 4]), Err(HokmError::GameFinished));
+    let card = Card::new(Rank::Ace, Suit::Spades);
+    assert_eq!(g.play_card(g.turn(), card), Err(HokmError::GameFinished));
+}
+
+#[test]
+fn legal_moves_match_the_rules() {
+    let mut trick = Trick::new(PlayerNumber::Two);
+    let hand: Hand = "AK2S 3H".parse().unwrap();
+    assert_eq!(legal_moves(&hand, &trick).len(), 4);
+    trick.played_cards[1] = Some(card("9S"));
+    assert_eq!(legal_moves(&hand, &trick), hand.cards_of_suit(Suit::Spades));
+    trick.played_cards[1] = Some(card("9D"));
+    assert_eq!(legal_moves(&hand, &trick), hand.cards());
+
+    for seed in 0..10 {
+        let p: &dyn Player = &RandomPlayer::with_seed(seed);
+        let mut g = Hokm::with_seed(PlayerNumber::One, seed);
+        while g.game_state() != GameState::Finished {
+            for i in 0..4 {
+                let player = PlayerNumber::from_index(i);
+                let legal = g.legal_moves(player);
+                if g.game_state() != GameState::NormalPlay || g.waiting_for() != Some(player) {
+                    assert!(legal.is_empty());
+                    continue;
+                }
+                for &c in g.player_state(player).hand().cards().iter() {
+                    assert_eq!(legal.contains(&c), g.check_play(player, c).is_ok());
+                }
+            }
+            g.play([p; 4]).unwrap();
+        }
+    }
+}
+
+fn card(s: &str) -> Card {
+    s.parse().unwrap()
+}
+
+fn parse_hands(hands: [&str; 4]) -> [Hand; 4] {
+    [
+        hands[0].parse().unwrap(),
+        hands[1].parse().unwrap(),
+        hands[2].parse().unwrap(),
+        hands[3].parse().unwrap(),
+    ]
+}
+
+const HANDS: [&str; 4] = [
+    "AKQJT98765432S",
+    "AKQJT98765432H",
+    "AKQJT98765432D",
+    "AKQJT98765432C",
+];
+
+#[test]
+fn predefined_deal() {
+    use PlayerNumber::*;
+    let first_five = [
+        card("2H"), card("AH"), card("5H"),
+        card("TH"), card("KH"),
+    ];
+    let deal = Deal::new(Two, parse_hands(HANDS), first_five).unwrap();
+    assert_eq!(deal.first_five(), &first_five);
+    assert_eq!(Deal::from_deck(Two, &deal.to_deck()), deal);
+
+    let mut g = Hokm::from_deal(deal.clone());
+    assert_eq!(g.deal(), Some(&deal));
+    assert_eq!(advance_to_decision(&mut g), Two);
+    assert_eq!(g.player_state(Two).hand().cards(), first_five);
+    g.set_trump_suit(Two, Suit::Hearts).unwrap();
+    advance_to_decision(&mut g);
+    for (i, s) in HANDS.iter().enumerate() {
+        assert_eq!(g.player_state(PlayerNumber::from_index(i)).hand().to_string(), *s);
+    }
+}
+
+#[test]
+fn invalid_deals() {
+    use PlayerNumber::*;
+    let first_five = [
+        card("AS"), card("KS"), card("QS"),
+        card("JS"), card("TS"),
+    ];
+    let mut hands = parse_hands(HANDS);
+    let last = hands[2].cards()[12];
+    hands[2].remove(last);
+    assert_eq!(Deal::new(One, hands, first_five), Err(DealError::WrongNumberOfCards(Three, 12)));
+    let mut hands = parse_hands(HANDS);
+    let (ours, theirs) = (hands[3].cards()[0], hands[0].cards()[0]);
+    hands[3].remove(ours);
+    hands[3].push(theirs);
+    assert_eq!(Deal::new(One, hands, first_five), Err(DealError::DuplicateCard(card("AS"))));
+    assert_eq!(Deal::new(Two, parse_hands(HANDS), first_five), Err(DealError::NotInHand(card("AS"))));
+    let first_five = [
+        card("AS"), card("AS"), card("KS"),
+        card("QS"), card("JS"),
+    ];
+    assert_eq!(Deal::new(One, parse_hands(HANDS), first_five), Err(DealError::DuplicateCard(card("AS"))));
+}
+
+fn saved(g: &Hokm) -> String {
+    let mut buf = Vec::new();
+    g.save(&mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn save_and_load() {
+    let p: &dyn Player = &SensiblePlayer::new();
+    let mut g = Hokm::with_ace_draw_and_seed(11);
+    let mut steps = 0;
+    loop {
+        let text = saved(&g);
+        let mut loaded = Hokm::load(text.as_bytes()).unwrap();
+        assert_eq!(saved(&loaded), text);
+        assert_eq!(loaded.game_state(), g.game_state());
+        assert_eq!(loaded.team_scores(), g.team_scores());
+        // Both games continue the same way
+        let event = g.play([p; 4]);
+        assert_eq!(loaded.play([p; 4]), event);
+        assert_eq!(saved(&loaded), saved(&g));
+        steps += 1;
+        if let Ok(GameEvent::Won(_)) = event {
+            break;
+        }
+    }
+    assert!(steps > 52);
+}
+
+// Version 1 listed the cards of completed tricks by player, and the tricks
+// each player had won
+#[test]
+fn load_version_1() {
+    let p: &dyn Player = &SensiblePlayer::new();
+    let mut g = Hokm::with_seed(PlayerNumber::Two, 8);
+    while g.completed_tricks().len() < 3 {
+        g.play([p; 4]).unwrap();
+    }
+    let text = saved(&g);
+    let mut old = Vec::new();
+    for line in text.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["hokm", _] => old.push("hokm 1".to_owned()),
+            ["completed", leader, ..] => {
+                let l: usize = leader.parse().unwrap();
+                let by_player: Vec<&str> = (0..4).map(|i| words[2 + (i + 5 - l) % 4]).collect();
+                old.push(format!("collected {} {}", leader, by_player.join(" ")));
+            }
+            ["hand", player, ..] => {
+                old.push(line.to_owned());
+                old.push(format!("score {} 1", player));
+            }
+            _ => old.push(line.to_owned()),
+        }
+    }
+    let loaded = Hokm::load(old.join("\n").as_bytes()).unwrap();
+    assert_eq!(saved(&loaded), text);
+    assert!(Hokm::load(old.join("\n").replace("hokm 1", "hokm 2").as_bytes()).is_err());
+}
+
+#[test]
+fn load_errors() {
+    let g = Hokm::with_seed(PlayerNumber::One, 5);
+    let text = saved(&g);
+    match Hokm::load(text.replace("hokm 2", "hokm 99").as_bytes()) {
+        Err(LoadError::UnsupportedVersion(v)) => assert_eq!(v, "99"),
+        _ => panic!("version not checked"),
+    }
+    match Hokm::load(text.replace("state DealingInitialFiveCards", "state Dealing").as_bytes()) {
+        Err(LoadError::Invalid(3, _)) => {},
+        _ => panic!("invalid state accepted"),
+    }
+    let duplicate = format!("{}hand 1 {}\n", text, g.deal().unwrap().hand(PlayerNumber::Two)[0]);
+    match Hokm::load(duplicate.as_bytes()) {
+        Err(LoadError::Inconsistent(_)) => {},
+        _ => panic!("duplicate card accepted"),
+    }
+    assert!(Hokm::load("".as_bytes()).is_err());
+}
+
+// A saved game has to account for every card, and the hands have to fit
+// the tricks played
+#[test]
+fn load_checks_cards() {
+    let p: &dyn Player = &SensiblePlayer::new();
+    let mut g = Hokm::with_seed(PlayerNumber::Three, 6);
+    while g.completed_tricks().len() < 2 {
+        g.play([p; 4]).unwrap();
+    }
+    let text = saved(&g);
+    let line = text.lines().find(|l| l.starts_with("completed")).unwrap();
+    match Hokm::load(text.replace(line, &format!("{} 1", line)).as_bytes()) {
+        Err(LoadError::Invalid(_, _)) => {},
+        _ => panic!("extra value accepted"),
+    }
+    let hand = text.lines().find(|l| l.starts_with("hand 1")).unwrap();
+    let (short, card) = hand.split_at(hand.rfind(' ').unwrap());
+    match Hokm::load(text.replace(hand, short).as_bytes()) {
+        Err(LoadError::Inconsistent(_)) => {},
+        _ => panic!("missing card accepted"),
+    }
+    let other = text.lines().find(|l| l.starts_with("hand 2")).unwrap();
+    let moved = text.replace(hand, short).replace(other, &format!("{}{}", other, card));
+    match Hokm::load(moved.as_bytes()) {
+        Err(LoadError::Inconsistent(_)) => {},
+        _ => panic!("wrong hand sizes accepted"),
+    }
+    assert!(Hokm::load(text.as_bytes()).is_ok());
+}
+
+#[test]
+fn completed_tricks() {
+    let p: &dyn Player = &SensiblePlayer::new();
+    let mut g = Hokm::with_seed(PlayerNumber::Two, 9);
+    let mut plays = Vec::new();
+    let mut winners = Vec::new();
+    loop {
+        match g.play([p; 4]).unwrap() {
+            GameEvent::PlayedCard(p, card) => plays.push((p, card)),
+            GameEvent::Scored(p) => winners.push(p),
+            GameEvent::Won(_) => break,
+            _ => {},
+        }
+    }
+    let tricks = g.completed_tricks();
+    assert_eq!(tricks.iter().map(|t| t.winner).collect::<Vec<_>>(), winners);
+    assert_eq!(tricks.iter().flat_map(|t| t.plays()).collect::<Vec<_>>(), plays);
+    for t in tricks {
+        assert_eq!(t.to_trick().complete(g.trump_suit().unwrap()), Some(*t));
+        assert_eq!(t.card_of(t.leader), t.cards[0]);
+    }
+    let (t13, t24) = g.team_scores();
+    assert_eq!((t13 + t24) as usize, tricks.len());
+    assert_eq!(g.tricks_won_by(Team::PlayersOneAndThree), t13);
+    assert_eq!(g.tricks_won(PlayerNumber::One) + g.tricks_won(PlayerNumber::Three), t13);
+    assert_eq!(g.played_cards().len(), plays.len());
+
+    // The winner of a completed trick is checked when loading
+    let text = saved(&g);
+    let line = text.lines().find(|l| l.starts_with("completed")).unwrap();
+    let (rest, winner) = line.split_at(line.len() - 1);
+    let other = if winner == "1" { "2" } else { "1" };
+    let tampered = text.replace(line, &format!("{}{}", rest, other));
+    match Hokm::load(tampered.as_bytes()) {
+        Err(LoadError::Inconsistent(_)) => {},
+        _ => panic!("wrong trick winner accepted"),
+    }
+}
+
+// Checks what it can see and plays the first legal card
+struct ViewChecker;
+
+impl Player for ViewChecker {
+    fn name(&self) -> String {
+        "View checker".to_owned()
+    }
+
+    fn call_trump_suit(&self, view: &PlayerView) -> Decision<Suit> {
+        assert_eq!(view.hakem(), view.seat());
+        assert_eq!(view.hand().len(), 5);
+        assert_eq!(view.trump_suit(), None);
+        Decision::Ready(view.hand().cards()[0].suit())
+    }
+
+    fn play(&self, view: &PlayerView) -> Decision<Card> {
+        assert_eq!(view.turn(), view.seat());
+        let others = (0..4).map(PlayerNumber::from_index).filter(|p| *p != view.seat());
+        assert_eq!(view.unseen_cards().len(), others.map(|p| view.hand_size(p)).sum());
+        let played = view.completed_tricks().len() * 4 + view.trick().unwrap().cards().len();
+        assert_eq!(view.played_cards().len(), played);
+        let (t13, t24) = view.team_scores();
+        assert_eq!((t13 + t24) as usize, view.completed_tricks().len());
+        Decision::Ready(view.legal_moves()[0])
+    }
+}
+
+#[test]
+fn player_view() {
+    for seed in 0..10 {
+        let mut g = Hokm::with_ace_draw_and_seed(seed);
+        while g.game_state() != GameState::Finished {
+            g.play([&ViewChecker; 4]).unwrap();
+        }
+        let view = g.view(PlayerNumber::Three);
+        assert_eq!(view.seat(), PlayerNumber::Three);
+        assert_eq!(view.hand().cards(), g.player_state(PlayerNumber::Three).hand().cards());
+        assert!((view.unseen_cards() & view.hand().to_set()).is_empty());
+        assert_eq!(view.legal_moves(), vec![]);
+    }
+}
+
+#[test]
+fn undo() {
+    let p: &dyn Player = &SensiblePlayer::new();
+    let mut g = Hokm::with_seed(PlayerNumber::Three, 8);
+    let mut record = GameRecord::new(&g);
+    assert_eq!(g.undo_card(), Err(HokmError::WrongPhase(GameState::DealingInitialFiveCards)));
+    // the game before each played card
+    let mut before = Vec::new();
+    loop {
+        if g.game_state() == GameState::NormalPlay && g.waiting_for().is_some() {
+            before.push(saved(&g));
+        }
+        let event = g.play([p; 4]).unwrap();
+        record.record(&g, event);
+        if let GameEvent::Won(_) = event {
+            break;
+        }
+    }
+    // The last trick is taken back at once, the rest card by card
+    let events = g.undo_trick().unwrap();
+    assert_eq!(events.len(), 4);
+    before.truncate(before.len() - 4);
+    for event in events {
+        record.record(&g, event);
+    }
+    assert_eq!(g.game_state(), GameState::NormalPlay);
+    assert_eq!(saved(&g), saved(&Hokm::load(saved(&g).as_bytes()).unwrap()));
+    while let Some(text) = before.pop() {
+        let event = g.undo_card().unwrap();
+        assert!(matches!(event, GameEvent::TookBack(..)));
+        record.record(&g, event);
+        assert_eq!(saved(&g), text);
+    }
+    assert_eq!(g.undo_card(), Err(HokmError::NothingToUndo));
+    assert_eq!(g.team_scores(), (0, 0));
+    assert!(record.tricks.is_empty() && record.result.is_none());
+
+    g.set_undo_allowed(false);
+    assert_eq!(g.undo_card(), Err(HokmError::UndoNotAllowed));
+    let loaded = Hokm::load(saved(&g).as_bytes()).unwrap();
+    assert!(!loaded.undo_allowed());
+}
+
+fn recorded_game(seed: u64) -> (GameRecord, Vec<GameEvent>) {
+    let p: &dyn Player = &SensiblePlayer::new();
+    let mut g = Hokm::with_ace_draw_and_seed(seed);
+    let mut record = GameRecord::new(&g);
+    let mut events = Vec::new();
+    loop {
+        let event = g.play([p; 4]).unwrap();
+        record.record(&g, event);
+        events.push(event);
+        if let GameEvent::Won(_) = event {
+            return (record, events);
+        }
+    }
+}
+
+#[test]
+fn game_record() {
+    let (record, events) = recorded_game(21);
+    assert_eq!(record.seed, Some(21));
+    assert_eq!(record.plays().count(), events.iter().filter(|e| matches!(e, GameEvent::PlayedCard(..))).count());
+    let mut text = Vec::new();
+    record.write(&mut text).unwrap();
+    let parsed = GameRecord::read(&text[..]).unwrap();
+    assert_eq!(parsed, record);
+
+    // Replaying the record gives the same events as the game itself
+    let mut replay = parsed.replay().unwrap();
+    let mut replayed = Vec::new();
+    while let Some(event) = replay.forward() {
+        replayed.push(event.unwrap());
+    }
+    let played = events.iter()
+        .skip_while(|e| **e != GameEvent::SortedHands)
+        .skip(1)
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(replayed, played);
+    assert_eq!(replay.hokm().determine_winner(), record.winner());
+
+    let end = replay.position();
+    replay.backward().unwrap();
+    assert_eq!(replay.position(), end - 1);
+    // The first trick is on the table but not yet collected
+    replay.seek(4).unwrap();
+    assert_eq!(replay.position(), 4);
+    assert_eq!(replay.hokm().trick().unwrap().played_cards.iter().flatten().count(), 4);
+    assert_eq!(replay.hokm().team_scores(), (0, 0));
+}
+
+#[derive(Default)]
+struct EventLog(Vec<GameEvent>);
+
+impl GameObserver for EventLog {
+    fn on_event(&mut self, _hokm: &Hokm, event: GameEvent) {
+        self.0.push(event);
+    }
+}
+
+#[test]
+fn observers() {
+    let (record, _) = recorded_game(23);
+    let p: &dyn Player = &SensiblePlayer::new();
+    let mut g = Hokm::with_ace_draw_and_seed(23);
+    let observed = Rc::new(RefCell::new(GameRecord::new(&g)));
+    g.add_observer(Box::new(observed.clone()));
+    while g.game_state() != GameState::Finished {
+        g.play([p; 4]).unwrap();
+    }
+    g.advance().unwrap();
+    assert_eq!(*observed.borrow(), record);
+
+    // Observers of a match see all of its hands
+    let log = Rc::new(RefCell::new(EventLog::default()));
+    let mut m = Match::with_seed(MatchRules::default(), 3);
+    m.add_observer(Box::new(log.clone()));
+    let mut played = Vec::new();
+    loop {
+        match m.play([p; 4]).unwrap() {
+            MatchEvent::Hand(event) => played.push(event),
+            MatchEvent::NewHand(_) => {},
+            MatchEvent::Won(_) => break,
+        }
+    }
+    assert_eq!(log.borrow().0, played);
+    assert_eq!(played.iter().filter(|e| matches!(e, GameEvent::Won(_))).count(), m.history().len());
+}
+
+// Decides like a sensible player, but only when asked the second time
+#[derive(Default)]
+struct Hesitant(Cell<bool>);
+
+impl Hesitant {
+    fn decide<T>(&self, decision: Decision<T>) -> Decision<T> {
+        match self.0.replace(false) {
+            true => decision,
+            false => {
+                self.0.set(true);
+                Decision::Pending
+            }
+        }
+    }
+}
+
+impl Player for Hesitant {
+    fn name(&self) -> String {
+        "Hesitant".to_owned()
+    }
+
+    fn call_trump_suit(&self, view: &PlayerView) -> Decision<Suit> {
+        self.decide(SensiblePlayer::new().call_trump_suit(view))
+    }
+
+    fn play(&self, view: &PlayerView) -> Decision<Card> {
+        self.decide(SensiblePlayer::new().play(view))
+    }
+}
+
+#[test]
+fn pending_decisions() {
+    let (record, events) = recorded_game(23);
+    let p = Hesitant::default();
+    let mut g = Hokm::with_ace_draw_and_seed(23);
+    let log = Rc::new(RefCell::new(EventLog::default()));
+    g.add_observer(Box::new(log.clone()));
+    let mut waiting = 0;
+    loop {
+        let turn = g.waiting_for();
+        match g.play([&p, &p, &p, &p]).unwrap() {
+            GameEvent::Waiting(player) => {
+                assert_eq!(Some(player), turn);
+                waiting += 1;
+            }
+            GameEvent::Won(_) => break,
+            _ => {},
+        }
+    }
+    assert_eq!(log.borrow().0, events);
+    assert_eq!(waiting, record.tricks.len() * 4 + 1);
+
+    // The decision of a pending player can be made outside of `play`
+    let sensible = SensiblePlayer::new();
+    let mut g = Hokm::with_ace_draw_and_seed(23);
+    let observed = Rc::new(RefCell::new(GameRecord::new(&g)));
+    g.add_observer(Box::new(observed.clone()));
+    loop {
+        let event = match g.play([&sensible, &sensible, &sensible, &Hesitant::default()]).unwrap() {
+            GameEvent::Waiting(p) if g.game_state() == GameState::SettingTrumpSuit => {
+                let suit = sensible.call_trump_suit(&g.view(p)).ready().unwrap();
+                g.set_trump_suit(p, suit).unwrap()
+            }
+            GameEvent::Waiting(p) => {
+                let card = sensible.play(&g.view(p)).ready().unwrap();
+                g.play_card(p, card).unwrap()
+            }
+            event => event,
+        };
+        if let GameEvent::Won(_) = event {
+            break;
+        }
+    }
+    assert_eq!(*observed.borrow(), record);
+}
+
+#[test]
+fn match_with_hand() {
+    let sensible = SensiblePlayer::new();
+    let human = Hesitant::default();
+    let start = || Match::with_hand(MatchRules::default(), Hokm::with_seed(PlayerNumber::Three, 5));
+    let mut m = start();
+    assert_eq!(m.seed(), 5);
+    let other = Match::with_trump_caller(MatchRules::default(), PlayerNumber::Two);
+    assert_eq!(other.hand().trump_caller(), PlayerNumber::Two);
+    loop {
+        let event = match m.play([&sensible, &sensible, &sensible, &human]).unwrap() {
+            MatchEvent::Hand(GameEvent::Waiting(p)) if m.hand().game_state() == GameState::SettingTrumpSuit => {
+                let suit = sensible.call_trump_suit(&m.hand().view(p)).ready().unwrap();
+                m.set_trump_suit(p, suit).unwrap()
+            }
+            MatchEvent::Hand(GameEvent::Waiting(p)) => {
+                let card = sensible.play(&m.hand().view(p)).ready().unwrap();
+                m.play_card(p, card).unwrap()
+            }
+            MatchEvent::Hand(event) => event,
+            event => panic!("unexpected {:?}", event),
+        };
+        if let GameEvent::Won(_) = event {
+            break;
+        }
+    }
+    assert_eq!(m.history().len(), 1);
+    assert_eq!(m.history()[0].trump_caller, PlayerNumber::Three);
+    assert!(matches!(m.play([&sensible, &sensible, &sensible, &human]), Ok(MatchEvent::NewHand(_))));
+    // the next hands follow from the seed of the first one
+    let mut other = start();
+    while let Ok(MatchEvent::Hand(_)) = other.play([&sensible; 4]) {}
+    assert_eq!(other.hand().seed(), m.hand().seed());
+}
+
+#[test]
+fn invalid_game_record() {
+    let (record, _) = recorded_game(22);
+    let mut text = Vec::new();
+    record.write(&mut text).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(GameRecord::read(text.replace("trick", "trik").as_bytes()).is_err());
+    assert_eq!(GameRecord::default().replay().err(), Some(HokmError::NoDeal));
+    // Swapping two cards of the first trick makes it illegal
+    let mut tampered = record.clone();
+    tampered.tricks[0].cards.swap(0, 1);
+    let mut replay = tampered.replay().unwrap();
+    let mut error = None;
+    while let Some(event) = replay.forward() {
+        if let Err(e) = event {
+            error = Some(e);
+            break;
+        }
+    }
+    assert!(error.is_some());
+}
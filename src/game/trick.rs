@@ -30,6 +30,11 @@ impl Trick {
         ]
     }
 
+    // The cards played so far
+    pub fn cards(&self) -> CardSet {
+        self.played_cards.iter().flatten().collect()
+    }
+
     pub fn have_all_played(&self) -> bool {
         !self.played_cards.iter().any(|pc| pc.is_none())
     }
@@ -57,4 +62,55 @@ impl Trick {
         }
         Some(PlayerNumber::from_index(w))
     }
+
+    // The completed trick, if everyone has played
+    pub fn complete(&self, trump_suit: Suit) -> Option<CompletedTrick> {
+        let winner = self.winner(trump_suit)?;
+        let mut cards = [self.first_card()?; 4];
+        for (card, played) in cards.iter_mut().zip(self.played_cards_in_order().iter()) {
+            *card = played.expect("everyone has played");
+        }
+        Some(CompletedTrick { leader: self.first_player, cards, winner })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompletedTrick {
+    pub leader: PlayerNumber,
+    // in play order, starting with the leader's card
+    pub cards: [Card; 4],
+    pub winner: PlayerNumber,
+}
+
+impl CompletedTrick {
+    // The players and their cards in play order
+    pub fn plays(&self) -> impl Iterator<Item = (PlayerNumber, Card)> + '_ {
+        let leader = self.leader.as_index();
+        self.cards.iter().enumerate().map(move |(i, c)| (PlayerNumber::from_index((leader + i) % 4), *c))
+    }
+
+    pub fn card_of(&self, player: PlayerNumber) -> Card {
+        self.cards[(player.as_index() + 4 - self.leader.as_index()) % 4]
+    }
+
+    pub fn card_set(&self) -> CardSet {
+        self.cards.iter().collect()
+    }
+
+    pub fn to_trick(&self) -> Trick {
+        let mut trick = Trick::new(self.leader);
+        for (p, card) in self.plays() {
+            trick.played_cards[p.as_index()] = Some(card);
+        }
+        trick
+    }
+}
+
+// The cards of the hand that can be played to the trick: the cards of the
+// suit that was led if the hand has any, otherwise all of them.
+pub fn legal_moves(hand: &Hand, trick: &Trick) -> Vec<Card> {
+    match trick.first_card() {
+        Some(first_card) if hand.count_of_suit(first_card.suit()) > 0 => hand.cards_of_suit(first_card.suit()),
+        _ => hand.cards().to_vec(),
+    }
 }
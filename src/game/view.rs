@@ -0,0 +1,43 @@
+
+use super::*;
+use crate::cards::*;
+
+// What a player may know about the game: their own hand and everything that
+// happened on the table, but not the other players' cards.
+#[derive(Clone, Copy)]
+pub struct PlayerView<'a> {
+    hokm: &'a Hokm,
+    seat: PlayerNumber,
+}
+
+impl<'a> PlayerView<'a> {
+    pub fn new(hokm: &'a Hokm, seat: PlayerNumber) -> Self {
+        PlayerView { hokm, seat }
+    }
+
+    pub fn seat(&self) -> PlayerNumber            { self.seat }
+    pub fn hand(&self) -> &'a Hand                { self.hokm.player_state(self.seat).hand() }
+    pub fn hakem(&self) -> PlayerNumber           { self.hokm.trump_caller() }
+    pub fn trump_suit(&self) -> Option<Suit>      { self.hokm.trump_suit() }
+    pub fn turn(&self) -> PlayerNumber            { self.hokm.turn() }
+    pub fn trick(&self) -> Option<&'a Trick>      { self.hokm.trick() }
+    pub fn team_scores(&self) -> (u32, u32)       { self.hokm.team_scores() }
+    pub fn completed_tricks(&self) -> &'a [CompletedTrick] { self.hokm.completed_tricks() }
+    pub fn played_cards(&self) -> CardSet         { self.hokm.played_cards() }
+    pub fn legal_moves(&self) -> Vec<Card>        { self.hokm.legal_moves(self.seat) }
+
+    pub fn tricks_won(&self, player: PlayerNumber) -> u32 {
+        self.hokm.tricks_won(player)
+    }
+
+    // The number of cards in a player's hand
+    pub fn hand_size(&self, player: PlayerNumber) -> usize {
+        self.hokm.player_state(player).hand().len()
+    }
+
+    // Cards in the other players' hands (or not dealt yet), i.e. neither
+    // in this player's hand nor played
+    pub fn unseen_cards(&self) -> CardSet {
+        !(self.played_cards() | self.hand().to_set())
+    }
+}
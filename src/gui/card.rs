@@ -23,6 +23,8 @@ pub struct GuiCard {
     pub scale: f64,
     pub state: SelectionState,
     pub alt_back: bool,
+    // painted darker, e.g. when the card cannot be played
+    pub dimmed: bool,
     pub on_click: Option<Arc<Fn(&mut GuiCard) -> Option<Card>>>,
 }
 
@@ -43,6 +45,7 @@ impl GuiCard {
             scale: DEFAULT_SCALE,
             state: SelectionState::None,
             alt_back: false,
+            dimmed: false,
             on_click: None,
         }
     }
@@ -53,7 +56,7 @@ impl GuiCard {
         (CARD_HEIGHT as f64 * self.scale) as u32
     }
 
-    fn covers_point(&self, x: i32, y: i32) -> bool {
+    pub fn covers_point(&self, x: i32, y: i32) -> bool {
         let center = Point::new(self.width() as i32 / 2, self.height() as i32 / 2);
         let (dx, dy) = (
             (self.x + center.x - x) as f64,
@@ -78,13 +81,17 @@ impl GuiCard {
         )
     }
 
+    // 20 pixels at the default scale
+    fn raise_height(&self) -> i32 {
+        (20.0 * self.scale / DEFAULT_SCALE) as i32
+    }
     pub fn raise(&mut self) -> MoveAnimation {
         self.state = SelectionState::Candidate;
-        self.move_around_self_axis(0, -20)
+        self.move_around_self_axis(0, -self.raise_height())
     }
     pub fn unraise(&mut self) -> MoveAnimation {
         self.state = SelectionState::None;
-        self.move_around_self_axis(0, 20)
+        self.move_around_self_axis(0, self.raise_height())
     }
 }
 
@@ -97,6 +104,7 @@ impl Paintable for GuiCard {
         let dest = Rect::new(self.x, self.y, self.width(), self.height());
         let center = Point::new(self.width() as i32 / 2, self.height() as i32 / 2);
         let (texture, src) = match (self.face_up, self.alt_back) {
+            (true, _) if self.dimmed => textures.dimmed_card_front(self.card),
             (true, _) => textures.card_front(self.card),
             (false, false) => textures.card_back(),
             (false, true) => textures.alt_back(),
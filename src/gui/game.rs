@@ -3,16 +3,18 @@ use super::*;
 use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::pixels::Color;
 use std::cell::RefCell;
+use std::rc::Rc;
 
-const HUMAN: PlayerNumber = PlayerNumber::Four;
-const MARGIN: u32 = 25;
+pub const HUMAN: PlayerNumber = PlayerNumber::Four;
+const MARGIN: i32 = 25;
 
 pub struct Game {
-    game: Hokm,
+    game: Match,
     gui_trick: Option<GuiTrick>,
     gui_hand: Option<GuiHand>,
     played_card: Option<(Animated<GuiCard>, usize)>,
     dealt_cards: Vec<(Animated<GuiCard>, usize)>,
+    drawn_cards: Vec<Animated<GuiCard>>,
     total_cards_dealt: usize,
     accept_click: bool,
     pausing_cycles: u32,
@@ -20,97 +22,276 @@ pub struct Game {
     deck_pile: Option<GuiPile>,
     player_piles: [Option<GuiPile>; 3],
     player_scores: [Option<GuiPlayerScore>; 4],
+    // shown while the human, as hakem, chooses the trump suit
+    trump_picker: Option<GuiSuitPicker>,
+    // a short message, e.g. when the human tries to play an illegal card
+    hint: Option<GuiMessage>,
+    hint_cycles: u32,
+    layout: Layout,
     arranged: bool,
     game_over: bool,
+    // only hands that are followed from the start are recorded
+    record: Option<Rc<RefCell<GameRecord>>>,
 
     players: [Box<Player>; 3],
     human_player: GuiPlayer,
 }
 
 impl Game {
-    pub fn new() -> Self {
-        let game = Hokm::new(PlayerNumber::One);
-        Game {
+    // The hakem of the first hand is chosen by an ace draw unless it is given
+    pub fn new(seed: Option<u64>, hakem: Option<PlayerNumber>) -> Self {
+        let rules = MatchRules::default();
+        let game = match (seed, hakem) {
+            (Some(seed), Some(hakem)) => Match::with_hand(rules, Hokm::with_seed(hakem, seed)),
+            (None, Some(hakem)) => Match::with_hand(rules, Hokm::new(hakem)),
+            (Some(seed), None) => Match::with_seed(rules, seed),
+            (None, None) => Match::new(rules),
+        };
+        println!("Seed: {}", game.seed());
+        Game::with_match(game)
+    }
+
+    // Continues a game, e.g. a hand that is loaded from a file
+    pub fn with_match(game: Match) -> Self {
+        let mut game = Game {
             game,
             gui_trick: None,
             gui_hand: None,
             played_card: None,
             dealt_cards: Vec::new(),
+            drawn_cards: Vec::new(),
             total_cards_dealt: 0,
             accept_click: true,
             pausing_cycles: 0,
             deck_pile: None,
             player_piles: [None, None, None],
             player_scores: [None, None, None, None],
+            trump_picker: None,
+            hint: None,
+            hint_cycles: 0,
+            layout: Layout::default(),
             arranged: false,
             game_over: false,
+            record: None,
             players: [
                 Box::new(SensiblePlayer::new()),
                 Box::new(SensiblePlayer::new()),
                 Box::new(SensiblePlayer::new()),
             ],
-            human_player: GuiPlayer { card: RefCell::new(None) },
+            human_player: GuiPlayer,
+        };
+        game.start_hand();
+        game
+    }
+
+    // Sets everything up for the hand the match is on
+    fn start_hand(&mut self) {
+        let hand = self.game.hand();
+        self.total_cards_dealt = match hand.deck_size() {
+            0 => 52,
+            _ => (0..4).map(|i| hand.player_state(i + 1).hand().len()).sum(),
+        };
+        let started = hand.team_scores() != (0, 0) ||
+            hand.trick().map_or(false, |t| t.played_cards.iter().any(Option::is_some));
+        // the observer stays with the match from one hand to the next
+        match (started, &self.record) {
+            (true, _) => self.record = None,
+            (false, Some(record)) => *record.borrow_mut() = GameRecord::new(hand),
+            (false, None) => {
+                let record = Rc::new(RefCell::new(GameRecord::new(hand)));
+                self.game.add_observer(Box::new(record.clone()));
+                self.record = Some(record);
+            }
+        }
+        self.gui_trick = None;
+        self.gui_hand = None;
+        self.played_card = None;
+        self.dealt_cards.clear();
+        self.drawn_cards.clear();
+        self.accept_click = true;
+        self.pausing_cycles = 0;
+        self.deck_pile = None;
+        self.player_piles = [None, None, None];
+        self.player_scores = [None, None, None, None];
+        self.trump_picker = None;
+        self.hint = None;
+        self.hint_cycles = 0;
+        self.arranged = false;
+        self.game_over = false;
+    }
+
+    // Deals the next hand of the match once the current one is over
+    pub fn next_hand(&mut self) -> bool {
+        if !self.game_over {
+            return false;
+        }
+        let players: [&dyn Player; 4] = [
+            self.players[0].as_ref(),
+            self.players[1].as_ref(),
+            self.players[2].as_ref(),
+            &self.human_player,
+        ];
+        match self.game.play(players) {
+            Ok(MatchEvent::NewHand(hakem)) => println!("New hand, hakem: {:?}", hakem),
+            Ok(event) => {
+                println!("Event: {:?}", event);
+                return false;
+            }
+            Err(e) => {
+                println!("Error: {}", e);
+                return false;
+            }
+        }
+        self.start_hand();
+        true
+    }
+
+    // The hand being played
+    pub fn hokm(&self) -> &Hokm {
+        self.game.hand()
+    }
+
+    pub fn game_match(&self) -> &Match {
+        &self.game
+    }
+
+    // Whether the hand is over and scored
+    pub fn is_over(&self) -> bool {
+        self.game_over
+    }
+
+    // The record of a finished game
+    pub fn record(&self) -> Option<GameRecord> {
+        match self.game_over {
+            true => self.record.as_ref().map(|r| r.borrow().clone()),
+            false => None,
         }
     }
 
+    // Takes back the cards played since the human's last card, including it
+    pub fn undo(&mut self) -> bool {
+        if self.game_over || self.pausing_cycles > 0 || self.played_card.is_some() {
+            return false;
+        }
+        if !self.game.hand().undo_allowed() || self.game.hand().player_state(HUMAN).hand().len() == 13 {
+            return false;
+        }
+        loop {
+            let event = match self.game.undo_card() {
+                Ok(event) => event,
+                Err(e) => {
+                    println!("Could not take back: {}", e);
+                    break;
+                }
+            };
+            println!("Event: {:?}", event);
+            if let GameEvent::TookBack(HUMAN, _) = event {
+                break;
+            }
+        }
+        self.arranged = false;
+        true
+    }
+
     fn set_gui_hand(&mut self, hand: Hand) {
-        let mut gui_hand = GuiHand::new(hand);
-        gui_hand.move_by(0, 8 * SCENE_HEIGHT as i32 / 17);
+        let mut gui_hand = GuiHand::new(hand, self.layout);
+        gui_hand.move_by(0, 8 * self.layout.height as i32 / 17);
+        // only the human's turn restricts the cards
+        let legal = self.game.hand().legal_moves(HUMAN);
+        gui_hand.set_legal_cards(match legal.is_empty() {
+            true => None,
+            false => Some(legal),
+        });
         self.gui_hand = Some(gui_hand);
     }
 
+    fn show_hint(&mut self, text: String) {
+        let mut hint = GuiMessage::new(text);
+        hint.set_position(self.layout.width as i32 / 2, 6 * self.layout.height as i32 / 10);
+        self.hint = Some(hint);
+        self.hint_cycles = 90;
+    }
+
+    // Everything is arranged again for the new window size. Cards that are
+    // moving are put where they are going right away.
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+        self.gui_hand = None;
+        self.gui_trick = None;
+        self.played_card = None;
+        self.dealt_cards.clear();
+        self.drawn_cards.clear();
+        if let Some(ref mut picker) = self.trump_picker {
+            *picker = GuiSuitPicker::new(layout);
+            picker.set_position(layout.width as i32 / 2, layout.height as i32 / 2);
+        }
+        self.arranged = false;
+    }
+
+    fn trick_position(&mut self, p: PlayerNumber) -> Point {
+        if let Some(ref mut gui_trick) = self.gui_trick {
+            return gui_trick.position_of(p.as_index()).unwrap();
+        }
+        let mut gui_trick = GuiTrick::new(Trick::new(p), self.layout);
+        gui_trick.move_by(0, -(self.layout.height as i32) / 40);
+        gui_trick.position_of(p.as_index()).unwrap()
+    }
+
     fn arrange_objects(&mut self) {
         self.arranged = true;
-        let psh = self.game.player_state(HUMAN);
-        if psh.hand().cards.len() > 0 {
+        let layout = self.layout;
+        let psh = self.game.hand().player_state(HUMAN);
+        if psh.hand().len() > 0 {
             self.set_gui_hand(psh.hand().to_owned());
         }
 
-        if let Some(trick) = self.game.trick() {
-            let mut gui_trick = GuiTrick::new(trick.to_owned());
-            gui_trick.move_by(0, -(SCENE_HEIGHT as i32) / 40);
+        if let Some(trick) = self.game.hand().trick() {
+            let mut gui_trick = GuiTrick::new(trick.to_owned(), layout);
+            gui_trick.move_by(0, -(layout.height as i32) / 40);
             self.gui_trick = Some(gui_trick);
         }
 
-        if self.game.deck_size() > 0 {
-            let mut dp = GuiPile::new(self.game.deck_size(), PileSpread::Deck);
-            dp.set_position(MARGIN as i32, (SCENE_HEIGHT - MARGIN - GuiPile::height()) as i32);
+        let (width, height) = (layout.width as i32, layout.height as i32);
+        let (pile_width, pile_height) = (GuiPile::width(&layout) as i32, GuiPile::height(&layout) as i32);
+        let (margin, gap) = (layout.scaled(MARGIN), layout.scaled(10));
+        if self.game.hand().deck_size() > 0 {
+            let mut dp = GuiPile::new(self.game.hand().deck_size(), PileSpread::Deck, layout);
+            dp.set_position(margin, height - margin - pile_height);
             self.deck_pile = Some(dp);
         }
 
-        let pyc = (SCENE_HEIGHT - GuiPile::height()) as i32 / 2;
-        let pxc = (SCENE_WIDTH - GuiPile::width()) as i32 / 2;
+        let pyc = (height - pile_height) / 2;
+        let pxc = (width - pile_width) / 2;
         for i in 0..3 {
-            let ps = self.game.player_state(PlayerNumber::from_index(i));
+            let ps = self.game.hand().player_state(PlayerNumber::from_index(i));
             let spread = match i % 2 {
                 0 => PileSpread::Vertical,
                 _ => PileSpread::Horizontal,
             };
             let (x, y) = match i {
-                0 => (MARGIN as i32, pyc),
-                1 => (pxc, MARGIN as i32),
-                2 => ((SCENE_WIDTH - GuiPile::width() - MARGIN) as i32, pyc),
+                0 => (margin, pyc),
+                1 => (pxc, margin),
+                2 => (width - pile_width - margin, pyc),
                 _ => unreachable!(),
             };
-            let mut pp = GuiPile::new(ps.hand().cards.len(), spread);
+            let mut pp = GuiPile::new(ps.hand().len(), spread, layout);
             pp.set_position(x, y);
             pp.name = Some(self.players[i].name());
             self.player_piles[i] = Some(pp);
         }
-        if [GameState::NormalPlay, GameState::Finished].contains(&self.game.game_state()) {
+        if [GameState::NormalPlay, GameState::Finished].contains(&self.game.hand().game_state()) {
             for i in 0..4 {
-                let ps = self.game.player_state(PlayerNumber::from_index(i));
                 let (orientation, x, y) = match i {
-                    0 => (Orientation::Vertical, (MARGIN + GuiPile::width() + 10) as i32, pyc + 10),
-                    1 => (Orientation::Horizontal, pxc + 10, (MARGIN + GuiPile::height() + 10) as i32),
-                    2 => (Orientation::Vertical, (SCENE_WIDTH - GuiPile::width() - MARGIN - 10) as i32, pyc + 10),
-                    3 => (Orientation::Horizontal, pxc + 10, (SCENE_HEIGHT - MARGIN) as i32),
+                    0 => (Orientation::Vertical, margin + pile_width + gap, pyc + gap),
+                    1 => (Orientation::Horizontal, pxc + gap, margin + pile_height + gap),
+                    2 => (Orientation::Vertical, width - pile_width - margin - gap, pyc + gap),
+                    3 => (Orientation::Horizontal, pxc + gap, height - margin),
                     _ => unreachable!(),
                 };
-                let mut psc = GuiPlayerScore::new(orientation);
+                let mut psc = GuiPlayerScore::new(orientation, layout);
                 psc.x = x;
                 psc.y = y;
-                psc.score = ps.score();
+                psc.score = self.game.hand().tricks_won(PlayerNumber::from_index(i));
                 self.player_scores[i] = Some(psc);
             }
         }
@@ -128,14 +309,25 @@ impl Game {
             ac.process();
             if ac.animations.is_empty() {
                 if pi == HUMAN.as_index() {
-                    let c = ac.object.card;
-                    self.human_player.card.replace(Some(c));
+                    let card = ac.object.card;
+                    match self.game.play_card(HUMAN, card) {
+                        Ok(event) => println!("Event: {:?}", event),
+                        // put the card back in the hand
+                        Err(e) => println!("Error: {}", e),
+                    }
                 }
                 self.arranged = false;
                 self.played_card = None;
             }
             return true;
         }
+        let mut drawing = false;
+        for ac in self.drawn_cards.iter_mut() {
+            drawing |= ac.process();
+        }
+        if drawing {
+            return true;
+        }
         self.dealt_cards.retain(|(dc, _)| !dc.animations.is_empty());
         for (ac, pi) in self.dealt_cards.iter_mut().rev() {
             let done = !ac.process();
@@ -163,6 +355,13 @@ impl Game {
 
 impl Paintable for Game {
     fn process(&mut self) -> bool {
+        if self.hint_cycles > 0 {
+            self.hint_cycles -= 1;
+            if self.hint_cycles == 0 {
+                self.hint = None;
+                return true;
+            }
+        }
         if self.pausing_cycles > 0 {
             self.pausing_cycles -= 1;
             if self.pausing_cycles == 0 {
@@ -177,30 +376,51 @@ impl Paintable for Game {
         if self.game_over {
             return false;
         }
-        if self.game.game_state() == GameState::Finished {
-            self.game_over = true;
-            self.arranged = false;
-            println!("Game over!");
-            return true;
-        }
-        let waiting_for_user = self.game.turn() == HUMAN &&
-            self.game.game_state() == GameState::NormalPlay &&
-            self.game.trick().map_or(false, |t| !t.have_all_played()) &&
-            self.human_player.card.borrow().is_none();
-
-        if waiting_for_user {
-            return false;
-        }
         let players: [&Player; 4] = [
             self.players[0].as_ref(),
             self.players[1].as_ref(),
             self.players[2].as_ref(),
             &self.human_player,
         ];
-        let event = self.game.play(players);
+        let event = match self.game.play(players) {
+            Ok(MatchEvent::Hand(event)) => event,
+            // the hand is over before a new one is dealt
+            Ok(event) => unreachable!("{:?}", event),
+            Err(e) => {
+                println!("Error: {}", e);
+                self.arranged = false;
+                return true;
+            }
+        };
+        if let GameEvent::Waiting(_) = event {
+            // the human has not decided yet
+            if self.game.hand().game_state() == GameState::SettingTrumpSuit && self.trump_picker.is_none() {
+                let mut picker = GuiSuitPicker::new(self.layout);
+                picker.set_position(self.layout.width as i32 / 2, self.layout.height as i32 / 2);
+                self.trump_picker = Some(picker);
+                return true;
+            }
+            return false;
+        }
         println!("Event: {:?}", event);
         match event {
+            GameEvent::DrewCard(p, card) => {
+                let pos = self.trick_position(p);
+                let dp = self.deck_pile.as_mut().unwrap();
+                let mut gc = dp.pop_card().unwrap();
+                gc.card = card;
+                let mut ac = Animated::new(gc);
+                ac.move_to(pos, 15);
+                ac.flip_card(15);
+                self.drawn_cards.push(ac);
+                true
+            },
+            GameEvent::ChoseTrumpCaller(_) => {
+                self.pausing_cycles = 60;
+                true
+            },
             GameEvent::DealtCards(p, n) => {
+                self.drawn_cards.clear();
                 self.total_cards_dealt += n;
                 if p == HUMAN {
                     if self.gui_hand.is_none() {
@@ -208,17 +428,17 @@ impl Paintable for Game {
                     }
                     let dp = self.deck_pile.as_mut().unwrap();
                     let gh = self.gui_hand.as_ref().unwrap();
-                    let ps = self.game.player_state(p);
-                    let ca = gh.card_arrangements(ps.hand().cards.len());
-                    let s = ps.hand().cards.len() - n;
-                    for (c, (pos, a)) in ps.hand().cards.iter().zip(ca).skip(s).rev() {
+                    let ps = self.game.hand().player_state(p);
+                    let ca = gh.card_arrangements(ps.hand().len());
+                    let s = ps.hand().len() - n;
+                    for (c, (pos, a)) in ps.hand().cards().iter().zip(ca).skip(s).rev() {
                         let mut gc = dp.pop_card().unwrap();
                         gc.card = *c;
                         let mut ac = Animated::new(gc);
                         ac.move_to(pos, 10);
                         ac.rotate_to(a, 10);
                         ac.flip_card(10);
-                        ac.scale_card(DEFAULT_SCALE, 10);
+                        ac.scale_card(self.layout.card_scale(), 10);
                         self.dealt_cards.push((ac, p.as_index()));
                     }
                     return true;
@@ -231,29 +451,21 @@ impl Paintable for Game {
                     ac.move_to(pp.get_position(pp.size() + i), 10);
                     self.dealt_cards.push((ac, p.as_index()));
                 }
-                return true;
+                true
             },
             GameEvent::SortedHands => {
                 self.arranged = false;
-                return true;
+                true
             },
             GameEvent::SetTrumpSuit(_) => {
                 self.arranged = false;
-                return true;
+                true
             },
             GameEvent::Scored(_) => {
                 self.pausing_cycles = 60;
-                return true;
-            },
-            GameEvent::InvalidPlay(_, _) => {
-                self.arranged = false;
-                return true;
+                true
             },
             GameEvent::PlayedCard(p, card) => {
-                if p == HUMAN {
-                    self.arranged = false;
-                    return true;
-                }
                 let gui_trick = self.gui_trick.as_mut().unwrap();
                 let pp = self.player_piles[p.as_index()].as_mut().unwrap();
                 let mut gc = pp.pop_card().unwrap();
@@ -264,12 +476,26 @@ impl Paintable for Game {
                 ac.rotate_to(180.0, steps);
                 ac.flip_card(steps);
                 self.played_card = Some((ac, p.as_index()));
-                return true;
+                true
+            },
+            GameEvent::TookBack(..) => {
+                self.arranged = false;
+                true
             },
             GameEvent::Won(_) => {
+                // the hand is scored in the match now
+                self.game_over = true;
                 self.accept_click = false;
-                return false;
+                self.arranged = false;
+                if let Some(ref record) = self.record {
+                    match save_record(&record.borrow()) {
+                        Ok(path) => println!("Game recorded in {}", path.display()),
+                        Err(e) => println!("Could not record the game: {}", e),
+                    }
+                }
+                true
             },
+            GameEvent::Waiting(_) => unreachable!(),
         }
     }
 
@@ -302,14 +528,25 @@ impl Paintable for Game {
         for (ac, _) in self.dealt_cards.iter_mut() {
             ac.paint(textures, canvas)?;
         }
-        if let Some(ts) = self.game.trump_suit() {
+        for ac in self.drawn_cards.iter_mut() {
+            ac.paint(textures, canvas)?;
+        }
+        if let Some(ref mut picker) = self.trump_picker {
+            picker.paint(textures, canvas)?;
+        }
+        if let Some(ref mut hint) = self.hint {
+            hint.paint(textures, canvas)?;
+        }
+        if let Some(ts) = self.game.hand().trump_suit() {
             let (t, src) = textures.suit(ts);
-            canvas.copy(t, src, Rect::new(10, 10, 30, 30))?;
+            let (pos, size) = (self.layout.scaled(10), self.layout.scaled(30) as u32);
+            canvas.copy(t, src, Rect::new(pos, pos, size, size))?;
         }
-        if [GameState::NormalPlay, GameState::Finished].contains(&self.game.game_state()) {
-            let scores = self.game.team_scores();
+        if [GameState::NormalPlay, GameState::Finished].contains(&self.game.hand().game_state()) {
+            let scores = self.game.hand().team_scores();
             let score_board = format!("{} - {}", scores.0, scores.1);
-            canvas.string(60, 25, &score_board, Color::RGB(255, 255, 255))?;
+            let (x, y) = (self.layout.scaled(60) as i16, self.layout.scaled(25) as i16);
+            canvas.string(x, y, &score_board, Color::RGB(255, 255, 255))?;
         }
         Ok(())
     }
@@ -320,39 +557,65 @@ impl Clickable for Game {
         if !self.accept_click || self.pausing_cycles > 0 || self.played_card.is_some() {
             return (false, None);
         }
+        if let Some(ref picker) = self.trump_picker {
+            let suit = match picker.suit_at(x, y) {
+                Some(suit) => suit,
+                None => return (false, None),
+            };
+            match self.game.set_trump_suit(HUMAN, suit) {
+                Ok(event) => println!("Event: {:?}", event),
+                Err(e) => println!("Error: {}", e),
+            }
+            self.trump_picker = None;
+            self.arranged = false;
+            return (true, None);
+        }
         if self.gui_hand.is_none() || self.gui_trick.is_none() {
             return (false, None);
         }
+        let gui_hand = self.gui_hand.as_ref().unwrap();
+        if let Some(card) = gui_hand.card_at(x, y).filter(|c| !gui_hand.is_legal(*c)) {
+            if let Err(HokmError::MustFollowSuit(_, _, suit)) = self.game.hand().check_play(HUMAN, card) {
+                self.show_hint(format!("You must follow suit: play {}", suit.name()));
+            }
+            return (true, None);
+        }
         let gui_hand = self.gui_hand.as_mut().unwrap();
         let gui_trick = self.gui_trick.as_mut().unwrap();
         let (handled, yielded_card) = gui_hand.click(x, y);
         if let Some(card) = yielded_card {
+            if !self.game.hand().legal_moves(HUMAN).contains(&card) {
+                // put the card back in the hand
+                self.show_hint("Wait for your turn".to_owned());
+                self.arranged = false;
+                return (true, None);
+            }
             let gc = gui_hand.pop_card(card).unwrap();
             let mut ac = Animated::new(gc);
             let steps = 15;
-            ac.move_to(gui_trick.position_of(self.game.turn().as_index()).unwrap(), steps);
+            ac.move_to(gui_trick.position_of(self.game.hand().turn().as_index()).unwrap(), steps);
             ac.rotate_to(180.0, steps);
-            ac.scale_card(SMALLER_CARDS, steps);
+            ac.scale_card(self.layout.smaller_cards(), steps);
             self.played_card = Some((ac, HUMAN.as_index()));
         }
-        return (handled, yielded_card);
+        (handled, yielded_card)
     }
 }
 
-struct GuiPlayer {
-    card: RefCell<Option<Card>>,
-}
+// The human's decisions are passed to `Match::set_trump_suit` and
+// `Match::play_card` when they click
+struct GuiPlayer;
 
 impl Player for GuiPlayer {
     fn name(&self) -> String {
         "Human".to_owned()
     }
 
-    fn call_trump_suit(&self, _hand: &Hand) -> Suit {
-        Suit::Spades // TODO
+    fn call_trump_suit(&self, _view: &PlayerView) -> Decision<Suit> {
+        Decision::Pending
     }
 
-    fn play(&self, _hand: &Hand, _trump_suit: Suit, _trick: &Trick) -> Card {
-        self.card.replace(None).unwrap()
+    fn play(&self, _view: &PlayerView) -> Decision<Card> {
+        Decision::Pending
     }
 }
@@ -11,17 +11,30 @@ pub struct GuiHand {
     animated_cards: Stacked<Animated<GuiCard>>,
     tx: i32,
     ty: i32,
+    layout: Layout,
+    // rotation around the center of the scene, e.g. to show other players' hands
+    rotation: f64,
+    pub scale: f64,
+    pub clickable: bool,
+    // cards that are not legal are dimmed and cannot be selected, all
+    // cards are legal if not set
+    legal_cards: Option<Vec<Card>>,
     arranged: bool,
     pub on_click: Option<Arc<Fn(&Hand, Card) -> Option<Hand>>>,
 }
 
 impl GuiHand {
-    pub fn new(hand: Hand) -> Self {
+    pub fn new(hand: Hand, layout: Layout) -> Self {
         GuiHand {
             hand,
             animated_cards: Stacked::new(),
             tx: 0,
             ty: 0,
+            layout,
+            rotation: 0.0,
+            scale: layout.card_scale(),
+            clickable: true,
+            legal_cards: None,
             arranged: false,
             on_click: None,
         }
@@ -31,11 +44,13 @@ impl GuiHand {
         self.arranged = true;
         let mut z = 0;
         self.animated_cards = Stacked::new();
-        let arr = self.card_arrangements(self.hand.cards.len());
-        for (c, (pos, angle)) in self.hand.cards.iter().zip(arr) {
+        let arr = self.card_arrangements(self.hand.len());
+        for (c, (pos, angle)) in self.hand.cards().iter().zip(arr) {
             let mut gc = GuiCard::new(*c);
             gc.set_position(pos.x, pos.y);
             gc.set_rotation(angle);
+            gc.scale = self.scale;
+            gc.dimmed = !self.is_legal(*c);
             gc.on_click = Some(Arc::new(|c: &mut GuiCard| {
                 match c.state {
                     SelectionState::None => c.state = SelectionState::Candidate,
@@ -50,10 +65,11 @@ impl GuiHand {
     }
 
     pub fn card_arrangements(&self, count: usize) -> Vec<(Point, f64)> {
+        let (width, height) = (self.layout.width, self.layout.height);
         let circle = Circle {
-            cx: (SCENE_WIDTH / 2) as i32 - (CARD_WIDTH as f64 * DEFAULT_SCALE / 2.0) as i32,
-            cy: (SCENE_HEIGHT / 2) as i32,
-            radius: min(5 * SCENE_WIDTH / 12, SCENE_HEIGHT / 4),
+            cx: (width / 2) as i32 - (CARD_WIDTH as f64 * self.scale / 2.0) as i32,
+            cy: (height / 2) as i32,
+            radius: min(5 * width / 12, height / 4),
         };
         let mut res = Vec::new();
         let mut angle = 4.0 * f32::consts::PI / 6.0;
@@ -63,17 +79,48 @@ impl GuiHand {
             let x = x + self.tx;
             let y = y + self.ty;
             let a = -(angle as f64).to_degrees() + 90.0;
-            res.push((Point::new(x, y), a));
+            res.push(self.rotate(Point::new(x, y), a));
             angle -= 4.0 * f32::consts::PI / 12.0 / 12.0;
         }
         res
     }
 
+    // Rotates a card around the center of the scene
+    fn rotate(&self, pos: Point, angle: f64) -> (Point, f64) {
+        if self.rotation == 0.0 {
+            return (pos, angle);
+        }
+        let (w, h) = (CARD_WIDTH as f64 * self.scale, CARD_HEIGHT as f64 * self.scale);
+        let (cx, cy) = (self.layout.width as f64 / 2.0, self.layout.height as f64 / 2.0);
+        let (dx, dy) = (pos.x as f64 + w / 2.0 - cx, pos.y as f64 + h / 2.0 - cy);
+        let theta = self.rotation.to_radians();
+        let x = cx + theta.cos() * dx - theta.sin() * dy - w / 2.0;
+        let y = cy + theta.sin() * dx + theta.cos() * dy - h / 2.0;
+        (Point::new(x as i32, y as i32), angle + self.rotation)
+    }
+
+    pub fn set_legal_cards(&mut self, cards: Option<Vec<Card>>) {
+        self.legal_cards = cards;
+        self.arranged = false;
+    }
+
+    pub fn is_legal(&self, card: Card) -> bool {
+        self.legal_cards.as_ref().map_or(true, |cards| cards.contains(&card))
+    }
+
+    // The topmost card at the point
+    pub fn card_at(&self, x: i32, y: i32) -> Option<Card> {
+        self.animated_cards.iter()
+            .filter(|ac| ac.object.covers_point(x, y))
+            .last()
+            .map(|ac| ac.object.card)
+    }
+
     pub fn pop_card(&mut self, card: Card) -> Option<GuiCard> {
         if !self.arranged {
             self.arrange_cards();
         }
-        self.hand.cards.retain(|c| *c != card);
+        self.hand.remove(card);
         self.arranged = false;
         self.animated_cards.iter()
             .find(|ac| ac.object.card == card)
@@ -81,7 +128,7 @@ impl GuiHand {
     }
 
     pub fn add_card(&mut self, card: Card) {
-        self.hand.cards.push(card);
+        self.hand.push(card);
         self.arranged = false;        
     }
 }
@@ -100,6 +147,9 @@ impl Paintable for GuiHand {
 
 impl Clickable for GuiHand {
     fn click(&mut self, x: i32, y: i32) -> (bool, Option<Card>) {
+        if !self.clickable {
+            return (false, None);
+        }
         let mut clicked_card = None;
         for ac in self.animated_cards.iter_mut_rev() {
             let (handled, _) = ac.click(x, y);
@@ -112,6 +162,9 @@ impl Clickable for GuiHand {
             Some(ac) => (ac.object.card, ac.object.state),
             None => return (false, None)
         };
+        if !self.is_legal(card) {
+            return (true, None);
+        }
         for ac in self.animated_cards.iter_mut() {
             if ac.object.card != card && ac.object.state != SelectionState::None {
                 let animation = ac.object.unraise();
@@ -152,3 +205,14 @@ impl Positioned for GuiHand {
         self.arranged = false;
     }
 }
+
+impl Rotatable for GuiHand {
+    fn get_rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    fn set_rotation(&mut self, angle: f64) {
+        self.rotation = angle;
+        self.arranged = false;
+    }
+}
@@ -0,0 +1,44 @@
+
+use super::*;
+use std::cmp::{max, min};
+
+// The size of the window's drawable area in pixels, which is larger than the
+// window size on HiDPI displays. The scene is designed for 800x800 and
+// everything is scaled with the smaller side of the window, while positions
+// spread over its whole width and height.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Layout {
+    pub fn new(width: u32, height: u32) -> Self {
+        Layout { width, height }
+    }
+
+    pub fn factor(&self) -> f64 {
+        min(self.width, self.height) as f64 / min(SCENE_WIDTH, SCENE_HEIGHT) as f64
+    }
+    // A length of the 800x800 design in this layout
+    pub fn scaled(&self, length: i32) -> i32 {
+        max(1, (length as f64 * self.factor()).round() as i32)
+    }
+
+    pub fn card_scale(&self) -> f64 {
+        DEFAULT_SCALE * self.factor()
+    }
+    pub fn smaller_cards(&self) -> f64 {
+        SMALLER_CARDS * self.factor()
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(self.width as i32 / 2, self.height as i32 / 2)
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::new(SCENE_WIDTH, SCENE_HEIGHT)
+    }
+}
@@ -1,53 +1,117 @@
 
-use sdl2::event::Event;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::image::InitFlag;
 use sdl2::keyboard::Keycode;
+use sdl2::render::WindowCanvas;
+use sdl2::video::FullscreenType;
 use std::time::Duration;
 
 use super::*;
 
-pub fn gui_main() -> Result<(), String> {
+#[derive(Clone, Debug, Default)]
+pub struct GuiOptions {
+    // deal a reproducible game
+    pub seed: Option<u64>,
+    // skip the ace draw and make this player the hakem
+    pub hakem: Option<PlayerNumber>,
+    // step through a recorded game instead of playing
+    pub replay: Option<PathBuf>,
+    pub fullscreen: bool,
+    // the theme manifest, the bundled theme if not set
+    pub theme: Option<PathBuf>,
+    pub four_color: bool,
+}
+
+pub fn gui_main(options: GuiOptions) -> Result<(), String> {
     let sdl_context = sdl2::init()?;
     let _image_context = sdl2::image::init(InitFlag::PNG | InitFlag::JPG)?;
     let video_subsystem = sdl_context.video()?;
     sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "1");
 
-    let window = video_subsystem
-        .window("Hokm", SCENE_WIDTH, SCENE_HEIGHT)
-        .position_centered()
-        .opengl()
-        .build()
-        .map_err(|e| e.to_string())?;
+    let mut window_builder = video_subsystem.window("Hokm", SCENE_WIDTH, SCENE_HEIGHT);
+    window_builder.position_centered().resizable().allow_highdpi().opengl();
+    if options.fullscreen {
+        window_builder.fullscreen_desktop();
+    }
+    let window = window_builder.build().map_err(|e| e.to_string())?;
 
     let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
     let texture_creator = canvas.texture_creator();
-    let textures = Textures::load(&texture_creator)?;
-    let mut scene = Scene::new();
+    let theme = Theme::load(options.theme.as_deref().unwrap_or_else(|| Path::new(DEFAULT_THEME)))?;
+    let mut textures = Textures::load(&texture_creator, theme, options.four_color)?;
+    let mut scene = Scene::new(&options, layout(&canvas)?)?;
     scene.paint(&textures, &mut canvas)?;
     let mut event_pump = sdl_context.event_pump()?;
-    let mut paused = false;
 
     'running: loop {
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. } |
-                Event::KeyDown { keycode: Some(Keycode::Escape), ..}
-                    => break 'running,
-                Event::KeyDown { keycode: Some(Keycode::Space), ..}
-                    => paused = !paused,
+                Event::KeyDown { keycode: Some(Keycode::Escape), ..} => {
+                    scene.save_unfinished_game()?;
+                    break 'running;
+                }
+                Event::KeyDown { keycode: Some(keycode), .. } if scene.key_down(keycode) => {
+                    scene.paint(&textures, &mut canvas)?;
+                }
+                Event::KeyDown { keycode: Some(Keycode::B), .. } => {
+                    textures.next_back();
+                    scene.paint(&textures, &mut canvas)?;
+                }
+                Event::KeyDown { keycode: Some(Keycode::G), .. } => {
+                    textures.next_background();
+                    scene.paint(&textures, &mut canvas)?;
+                }
+                Event::KeyDown { keycode: Some(Keycode::C), .. } => {
+                    let four_color = !textures.four_color();
+                    textures.set_four_color(&texture_creator, four_color)?;
+                    scene.paint(&textures, &mut canvas)?;
+                }
+                Event::KeyDown { keycode: Some(Keycode::F11), .. } => {
+                    let window = canvas.window_mut();
+                    let fullscreen = match window.fullscreen_state() {
+                        FullscreenType::Off => FullscreenType::Desktop,
+                        _ => FullscreenType::Off,
+                    };
+                    window.set_fullscreen(fullscreen)?;
+                }
+                Event::Window { win_event: WindowEvent::SizeChanged(..), .. } => {
+                    scene.set_layout(layout(&canvas)?);
+                    scene.paint(&textures, &mut canvas)?;
+                }
                 Event::MouseButtonDown { x, y, .. } => {
+                    let (x, y) = to_pixels(&canvas, x, y);
                     scene.click(x, y);
                     scene.paint(&textures, &mut canvas)?;
                 }
                 _ => {}
             }
         }
+        if scene.quit() {
+            scene.save_unfinished_game()?;
+            break 'running;
+        }
         ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
         // The rest of the game loop goes here...
-        if !paused && scene.process() {
+        if scene.process() {
             scene.paint(&textures, &mut canvas)?;
         }
     }
 
     Ok(())
 }
+
+fn layout(canvas: &WindowCanvas) -> Result<Layout, String> {
+    let (width, height) = canvas.output_size()?;
+    Ok(Layout::new(width, height))
+}
+
+// Mouse events are in window coordinates, which are smaller than pixels on
+// HiDPI displays
+fn to_pixels(canvas: &WindowCanvas, x: i32, y: i32) -> (i32, i32) {
+    let (w, h) = canvas.window().size();
+    match canvas.output_size() {
+        Ok((pw, ph)) if w > 0 && h > 0 => (x * pw as i32 / w as i32, y * ph as i32 / h as i32),
+        _ => (x, y),
+    }
+}
@@ -0,0 +1,145 @@
+
+use super::*;
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+
+const CHAR_SIZE: i32 = 8;
+const MENU_PADDING: i32 = 20;
+const LINE_HEIGHT: i32 = 20;
+const BUTTON_HEIGHT: i32 = 28;
+const BUTTON_GAP: i32 = 8;
+
+// What a menu button does, each one has a key too
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    Resume,
+    NextHand,
+    Replay,
+    NewGame,
+    Quit,
+}
+
+impl MenuAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            MenuAction::Resume => "Resume (Space)",
+            MenuAction::NextHand => "Next hand (Enter)",
+            MenuAction::Replay => "Replay this hand (R)",
+            MenuAction::NewGame => "New game (N)",
+            MenuAction::Quit => "Quit (Esc)",
+        }
+    }
+
+    pub fn key(self) -> Keycode {
+        match self {
+            MenuAction::Resume => Keycode::Space,
+            MenuAction::NextHand => Keycode::Return,
+            MenuAction::Replay => Keycode::R,
+            MenuAction::NewGame => Keycode::N,
+            MenuAction::Quit => Keycode::Escape,
+        }
+    }
+}
+
+// A title, a few lines of text and a column of buttons, in a box centered
+// around its position
+#[derive(Clone, Debug)]
+pub struct GuiMenu {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub actions: Vec<MenuAction>,
+    x: i32,
+    y: i32,
+}
+
+impl GuiMenu {
+    pub fn new(title: String, lines: Vec<String>, actions: Vec<MenuAction>) -> Self {
+        GuiMenu {
+            title,
+            lines,
+            actions,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    fn text_width(text: &str) -> i32 {
+        text.chars().count() as i32 * CHAR_SIZE
+    }
+
+    fn button_width(&self) -> i32 {
+        let widest = self.actions.iter().map(|a| GuiMenu::text_width(a.label())).max().unwrap_or(0);
+        widest + 2 * MENU_PADDING
+    }
+
+    fn width(&self) -> i32 {
+        let texts = self.lines.iter().chain(Some(&self.title)).map(|t| GuiMenu::text_width(t));
+        texts.max().unwrap_or(0).max(self.button_width()) + 2 * MENU_PADDING
+    }
+
+    fn height(&self) -> i32 {
+        let text = (self.lines.len() as i32 + 1) * LINE_HEIGHT;
+        let buttons = self.actions.len() as i32 * (BUTTON_HEIGHT + BUTTON_GAP);
+        text + buttons + 2 * MENU_PADDING
+    }
+
+    fn rect(&self) -> Rect {
+        let (w, h) = (self.width(), self.height());
+        Rect::new(self.x - w / 2, self.y - h / 2, w as u32, h as u32)
+    }
+
+    fn button_rect(&self, i: usize) -> Rect {
+        let top = self.rect().top() + MENU_PADDING + (self.lines.len() as i32 + 1) * LINE_HEIGHT + BUTTON_GAP;
+        let w = self.button_width();
+        Rect::new(self.x - w / 2, top + i as i32 * (BUTTON_HEIGHT + BUTTON_GAP), w as u32, BUTTON_HEIGHT as u32)
+    }
+
+    // The action of the button at the point, if any
+    pub fn action_at(&self, x: i32, y: i32) -> Option<MenuAction> {
+        (0..self.actions.len()).find(|&i| self.button_rect(i).contains_point((x, y))).map(|i| self.actions[i])
+    }
+
+    pub fn action_for(&self, keycode: Keycode) -> Option<MenuAction> {
+        self.actions.iter().copied().find(|a| a.key() == keycode)
+    }
+}
+
+impl Paintable for GuiMenu {
+    fn process(&mut self) -> bool { false }
+
+    fn paint(&mut self, _textures: &Textures, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let white = Color::RGB(255, 255, 255);
+        let rect = self.rect();
+        canvas.box_(rect.left() as i16, rect.top() as i16, rect.right() as i16, rect.bottom() as i16, Color::RGBA(0, 0, 0, 220))?;
+        let mut y = rect.top() + MENU_PADDING;
+        for (i, text) in Some(&self.title).into_iter().chain(self.lines.iter()).enumerate() {
+            let x = self.x - GuiMenu::text_width(text) / 2;
+            let color = match i {
+                0 => Color::RGB(255, 215, 0),
+                _ => white,
+            };
+            canvas.string(x as i16, y as i16, text, color)?;
+            y += LINE_HEIGHT;
+        }
+        for (i, action) in self.actions.iter().enumerate() {
+            let r = self.button_rect(i);
+            let (x1, y1, x2, y2) = (r.left() as i16, r.top() as i16, r.right() as i16, r.bottom() as i16);
+            canvas.rounded_box(x1, y1, x2, y2, 5, Color::RGBA(60, 60, 60, 255))?;
+            canvas.rounded_rectangle(x1, y1, x2, y2, 5, white)?;
+            let x = self.x - GuiMenu::text_width(action.label()) / 2;
+            canvas.string(x as i16, (r.top() + (BUTTON_HEIGHT - CHAR_SIZE) / 2) as i16, action.label(), white)?;
+        }
+        Ok(())
+    }
+}
+
+impl Positioned for GuiMenu {
+    fn get_position(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    fn set_position(&mut self, x: i32, y: i32) {
+        self.x = x;
+        self.y = y;
+    }
+}
@@ -3,6 +3,10 @@ use super::*;
 use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::pixels::Color;
 
+const SUIT_ICON_SIZE: i32 = 60;
+const SUIT_PICKER_PADDING: i32 = 15;
+const SUIT_PICKER_TITLE: &str = "Choose the trump suit";
+
 pub struct Circle {
     pub cx: i32,
     pub cy: i32,
@@ -29,15 +33,17 @@ pub struct GuiPlayerScore {
     pub x: i32,
     pub y: i32,
     pub orientation: Orientation,
+    layout: Layout,
 }
 
 impl GuiPlayerScore {
-    pub fn new(orientation: Orientation) -> Self {
+    pub fn new(orientation: Orientation, layout: Layout) -> Self {
         GuiPlayerScore {
             score: 0,
             x: 0,
             y: 0,
             orientation,
+            layout,
         }
     }
 }
@@ -49,12 +55,13 @@ impl Paintable for GuiPlayerScore {
 
     fn paint(&mut self, _textures: &Textures, canvas: &mut WindowCanvas) -> Result<(), String> {
         let color = Color::RGB(255, 255, 255);
+        let spacing = self.layout.scaled(10) as i16;
         for i in 0..7 {
             let (x, y) = match self.orientation {
-                Orientation::Horizontal => (self.x as i16 + i * 10, self.y as i16),
-                Orientation::Vertical => (self.x as i16, self.y as i16 + i * 10),
+                Orientation::Horizontal => (self.x as i16 + i * spacing, self.y as i16),
+                Orientation::Vertical => (self.x as i16, self.y as i16 + i * spacing),
             };
-            let radius = 3;
+            let radius = self.layout.scaled(3) as i16;
             if (i as u32) < self.score {
                 canvas.filled_circle(x, y, radius, color)?;
             } else {
@@ -135,3 +142,108 @@ impl Paintable for GuiCardOrPlaceHolder {
         }
     }
 }
+
+// A line of text in a box, centered around its position
+#[derive(Clone, Debug)]
+pub struct GuiMessage {
+    pub text: String,
+    x: i32,
+    y: i32,
+}
+
+impl GuiMessage {
+    pub fn new(text: String) -> Self {
+        GuiMessage {
+            text,
+            x: 0,
+            y: 0,
+        }
+    }
+}
+
+impl Paintable for GuiMessage {
+    fn process(&mut self) -> bool { false }
+
+    fn paint(&mut self, _textures: &Textures, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let (padding, char_size) = (12, 8);
+        let w = self.text.chars().count() as i16 * char_size;
+        let (x, y) = (self.x as i16 - w / 2, self.y as i16 - char_size / 2);
+        canvas.box_(x - padding, y - padding, x + w + padding, y + char_size + padding, Color::RGBA(0, 0, 0, 200))?;
+        canvas.string(x, y, &self.text, Color::RGB(255, 255, 255))?;
+        Ok(())
+    }
+}
+
+impl Positioned for GuiMessage {
+    fn get_position(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    fn set_position(&mut self, x: i32, y: i32) {
+        self.x = x;
+        self.y = y;
+    }
+}
+
+// The four suits to choose the trump suit from, in a box centered around
+// its position
+#[derive(Clone, Debug)]
+pub struct GuiSuitPicker {
+    x: i32,
+    y: i32,
+    layout: Layout,
+}
+
+impl GuiSuitPicker {
+    pub fn new(layout: Layout) -> Self {
+        GuiSuitPicker {
+            x: 0,
+            y: 0,
+            layout,
+        }
+    }
+
+    fn icon_rect(&self, i: usize) -> Rect {
+        let (icon, padding) = (self.layout.scaled(SUIT_ICON_SIZE), self.layout.scaled(SUIT_PICKER_PADDING));
+        let size = icon + padding;
+        let x = self.x - 2 * size + i as i32 * size + padding / 2;
+        let y = self.y - icon / 2 + padding;
+        Rect::new(x, y, icon as u32, icon as u32)
+    }
+
+    // The suit whose icon is at the point, if any
+    pub fn suit_at(&self, x: i32, y: i32) -> Option<Suit> {
+        let suits = Suit::all_suits();
+        (0..suits.len()).find(|&i| self.icon_rect(i).contains_point((x, y))).map(|i| suits[i])
+    }
+}
+
+impl Paintable for GuiSuitPicker {
+    fn process(&mut self) -> bool { false }
+
+    fn paint(&mut self, textures: &Textures, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let (first, last) = (self.icon_rect(0), self.icon_rect(3));
+        let padding = self.layout.scaled(SUIT_PICKER_PADDING);
+        let (x1, y1) = (first.left() - padding, first.top() - 2 * padding);
+        let (x2, y2) = (last.right() + padding, last.bottom() + padding);
+        canvas.box_(x1 as i16, y1 as i16, x2 as i16, y2 as i16, Color::RGBA(0, 0, 0, 200))?;
+        let title_x = self.x - SUIT_PICKER_TITLE.len() as i32 * 8 / 2;
+        canvas.string(title_x as i16, (y1 + padding / 2) as i16, SUIT_PICKER_TITLE, Color::RGB(255, 255, 255))?;
+        for (i, &suit) in Suit::all_suits().iter().enumerate() {
+            let (t, src) = textures.suit(suit);
+            canvas.copy(t, src, self.icon_rect(i))?;
+        }
+        Ok(())
+    }
+}
+
+impl Positioned for GuiSuitPicker {
+    fn get_position(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    fn set_position(&mut self, x: i32, y: i32) {
+        self.x = x;
+        self.y = y;
+    }
+}
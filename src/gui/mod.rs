@@ -2,30 +2,49 @@
 use crate::cards::*;
 use crate::game::*;
 use crate::players::*;
+use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::image::LoadTexture;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
 use sdl2::render::Texture;
 use sdl2::render::TextureCreator;
 use sdl2::render::WindowCanvas;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 mod animation;
 mod card;
 mod game;
 mod hand;
+mod layout;
 mod main;
+mod menu;
 mod misc;
 mod pile;
+mod replay;
+mod theme;
 mod trick;
+#[cfg(test)]
+mod tests;
 
 use animation::*;
 use card::*;
 use game::*;
 use hand::*;
-pub use main::gui_main;
+use layout::*;
+pub use main::{gui_main, GuiOptions};
+use menu::*;
 use misc::*;
 use pile::*;
+use replay::*;
+pub use theme::{Theme, DEFAULT_THEME};
 use trick::*;
 
+// the size of the window when it opens, which the scene is designed for
 const SCENE_WIDTH: u32 = 800;
 const SCENE_HEIGHT: u32 = 800;
 const CARD_WIDTH: u32 = 226;
@@ -82,51 +101,77 @@ impl<T, U> AnimationClone<T> for U where U: Animation<T> + 'static + Clone {
 }
 
 pub struct Textures<'a> {
+    theme: Theme,
+    four_color: bool,
     pub card_fronts: Texture<'a>,
-    pub card_back: Texture<'a>,
-    pub alt_back: Texture<'a>,
+    // for cards that cannot be played
+    pub dimmed_card_fronts: Texture<'a>,
     pub suits: Texture<'a>,
-    pub background: Texture<'a>,
+    backs: Vec<Texture<'a>>,
+    back: usize,
+    backgrounds: Vec<Texture<'a>>,
+    background: usize,
 }
 
 impl<'a> Textures<'a> {
-    pub fn load<T>(texture_creator: &'a TextureCreator<T>) -> Result<Self, String> {
+    pub fn load<T>(texture_creator: &'a TextureCreator<T>, theme: Theme, four_color: bool) -> Result<Self, String> {
+        let load_all = |paths: &[PathBuf]| -> Result<Vec<Texture<'a>>, String> {
+            paths.iter().map(|path| texture_creator.load_texture(path)).collect()
+        };
+        let mut dimmed_card_fronts = theme.load_fronts(texture_creator, four_color)?;
+        dimmed_card_fronts.set_color_mod(140, 140, 140);
         Ok(Textures {
-            card_fronts: texture_creator.load_texture("res/cards.png")?,
-            card_back: texture_creator.load_texture("res/back-3.png")?,
-            alt_back: texture_creator.load_texture("res/back-2.png")?,
-            suits: texture_creator.load_texture("res/suits.png")?,
-            background: texture_creator.load_texture("res/background-2.png")?,
+            card_fronts: theme.load_fronts(texture_creator, four_color)?,
+            dimmed_card_fronts,
+            suits: theme.load_suits(texture_creator, four_color)?,
+            backs: load_all(&theme.backs)?,
+            back: 0,
+            backgrounds: load_all(&theme.backgrounds)?,
+            background: 0,
+            theme,
+            four_color,
         })
     }
 
+    // Loads the card fronts and suits again in four colors, or the usual two
+    pub fn set_four_color<T>(&mut self, texture_creator: &'a TextureCreator<T>, four_color: bool) -> Result<(), String> {
+        let mut dimmed_card_fronts = self.theme.load_fronts(texture_creator, four_color)?;
+        dimmed_card_fronts.set_color_mod(140, 140, 140);
+        self.card_fronts = self.theme.load_fronts(texture_creator, four_color)?;
+        self.dimmed_card_fronts = dimmed_card_fronts;
+        self.suits = self.theme.load_suits(texture_creator, four_color)?;
+        self.four_color = four_color;
+        Ok(())
+    }
+    pub fn four_color(&self) -> bool {
+        self.four_color
+    }
+
+    pub fn next_back(&mut self) {
+        self.back = (self.back + 1) % self.backs.len();
+    }
+    pub fn next_background(&mut self) {
+        self.background = (self.background + 1) % self.backgrounds.len();
+    }
+
     pub fn card_front(&self, card: Card) -> (&Texture<'a>, Option<Rect>) {
-        let rx = match card.rank().as_i32() {
-            14 => 0,
-            x => (x - 1) * 265,
-        };
-        let ry = match card.suit() {
-            Suit::Clubs => 0,
-            Suit::Hearts => 354,
-            Suit::Spades => 354 * 2,
-            Suit::Diamonds => 354 * 3,
-        };
-        (&self.card_fronts, Some(Rect::new(rx, ry, CARD_WIDTH, CARD_HEIGHT)))
+        (&self.card_fronts, Some(self.theme.front(card)))
+    }
+    pub fn dimmed_card_front(&self, card: Card) -> (&Texture<'a>, Option<Rect>) {
+        (&self.dimmed_card_fronts, Some(self.theme.front(card)))
     }
     pub fn card_back(&self) -> (&Texture<'a>, Option<Rect>) {
-        (&self.card_back, None)
+        (&self.backs[self.back], None)
     }
+    // the back after the chosen one
     pub fn alt_back(&self) -> (&Texture<'a>, Option<Rect>) {
-        (&self.alt_back, None)
+        (&self.backs[(self.back + 1) % self.backs.len()], None)
     }
     pub fn suit(&self, suit: Suit) -> (&Texture<'a>, Option<Rect>) {
-        let rx = match suit {
-            Suit::Hearts => 0,
-            Suit::Clubs => 60,
-            Suit::Diamonds => 120,
-            Suit::Spades => 180,
-        };
-        (&self.suits, Some(Rect::new(rx, 0, 60, 60)))
+        (&self.suits, Some(self.theme.suit(suit)))
+    }
+    pub fn background(&self) -> &Texture<'a> {
+        &self.backgrounds[self.background]
     }
 }
 
@@ -265,25 +310,286 @@ impl<T: Clickable> Clickable for Animated<T> {
     }
 }
 
+// An unfinished game is saved here when the GUI is closed
+const SAVE_FILE: &str = "hokm-save.txt";
+// Finished games are recorded here
+const RECORDS_DIR: &str = "records";
+
+fn save_record(record: &GameRecord) -> Result<PathBuf, String> {
+    let id = match record.seed {
+        Some(seed) => seed,
+        None => SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?.as_secs(),
+    };
+    fs::create_dir_all(RECORDS_DIR).map_err(|e| e.to_string())?;
+    let path = Path::new(RECORDS_DIR).join(format!("game-{}.txt", id));
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    record.write(BufWriter::new(file)).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+enum View {
+    Game(Box<Game>),
+    Replay(Box<ReplayViewer>),
+}
+
 pub struct Scene {
-    game: Game,
+    view: View,
+    layout: Layout,
+    // the saved game, until the user decides whether to resume it
+    saved_game: Option<Hokm>,
+    prompt: GuiMessage,
+    // the pause menu or the game-over screen
+    menu: Option<GuiMenu>,
+    paused: bool,
+    // a game whose last hand is being replayed, to deal its next hand after
+    finished_game: Option<Box<Game>>,
+    quit: bool,
 }
 
 impl Scene {
-    pub fn new() -> Scene {
-        Scene { game: Game::new() }
+    pub fn new(options: &GuiOptions, layout: Layout) -> Result<Scene, String> {
+        let prompt = GuiMessage::new("Resume the last unfinished game? (Y/N)".to_owned());
+        let (view, saved_game) = match options.replay {
+            Some(ref path) => (View::Replay(Box::new(ReplayViewer::load(path)?)), None),
+            None => (
+                View::Game(Box::new(Game::new(options.seed, options.hakem))),
+                // Only offer to resume if no specific game is requested
+                match (options.seed, options.hakem) {
+                    (None, None) => load_saved_game(),
+                    _ => None,
+                },
+            ),
+        };
+        let mut scene = Scene {
+            view,
+            layout,
+            saved_game,
+            prompt,
+            menu: None,
+            paused: false,
+            finished_game: None,
+            quit: false,
+        };
+        scene.set_layout(layout);
+        Ok(scene)
+    }
+
+    // Called when the size of the window changes
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+        self.prompt.set_position(layout.width as i32 / 2, layout.height as i32 / 2);
+        if let Some(ref mut menu) = self.menu {
+            menu.set_position(layout.width as i32 / 2, layout.height as i32 / 2);
+        }
+        match self.view {
+            View::Game(ref mut game) => game.set_layout(layout),
+            View::Replay(ref mut viewer) => viewer.set_layout(layout),
+        }
+    }
+
+    // Whether the user chose to quit from a menu
+    pub fn quit(&self) -> bool {
+        self.quit
+    }
+
+    // returns true if the key is handled
+    pub fn key_down(&mut self, keycode: Keycode) -> bool {
+        if self.saved_game.is_none() {
+            if let Some(action) = self.menu.as_ref().and_then(|m| m.action_for(keycode)) {
+                return self.menu_action(action);
+            }
+            if self.menu.is_some() {
+                return false;
+            }
+            return match self.view {
+                _ if keycode == Keycode::Space => self.pause(),
+                View::Game(ref mut game) if keycode == Keycode::Backspace => game.undo(),
+                View::Game(_) => false,
+                View::Replay(ref mut viewer) => viewer.key_down(keycode),
+            };
+        }
+        match keycode {
+            Keycode::Y => {
+                let hand = self.saved_game.take().unwrap();
+                let mut game = Game::with_match(Match::with_hand(MatchRules::default(), hand));
+                game.set_layout(self.layout);
+                self.view = View::Game(Box::new(game));
+                true
+            }
+            Keycode::N => {
+                self.saved_game = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn show_menu(&mut self, mut menu: GuiMenu) {
+        menu.set_position(self.layout.width as i32 / 2, self.layout.height as i32 / 2);
+        self.menu = Some(menu);
+    }
+
+    fn pause(&mut self) -> bool {
+        let mut actions = vec![MenuAction::Resume];
+        if self.finished_game.is_some() {
+            actions.push(MenuAction::NextHand);
+        }
+        actions.extend(&[MenuAction::NewGame, MenuAction::Quit]);
+        self.show_menu(GuiMenu::new("Paused".to_owned(), Vec::new(), actions));
+        self.paused = true;
+        true
+    }
+
+    // The result of the hand that is over and the score of the match
+    fn game_over_menu(game: &Game) -> GuiMenu {
+        let m = game.game_match();
+        let us = HUMAN.team();
+        let ours = |(t13, t24): (u32, u32)| match us {
+            Team::PlayersOneAndThree => (t13, t24),
+            Team::PlayersTwoAndFour => (t24, t13),
+        };
+        let mut lines = Vec::new();
+        let title = match (m.determine_winner(), m.last_hand()) {
+            (Some(team), _) if team == us => "Your team wins the match!",
+            (Some(_), _) => "The other team wins the match",
+            (None, Some(hand)) if hand.winner == us => "Your team wins the hand!",
+            (None, _) => "The other team wins the hand",
+        };
+        if let Some(hand) = m.last_hand() {
+            let (ours, theirs) = ours(hand.tricks);
+            lines.push(format!("Tricks: {} to {}", ours, theirs));
+            match hand.kot {
+                Some(Kot::Kot) => lines.push("Kot!".to_owned()),
+                Some(Kot::HakemKot) => lines.push("Hakem kot!".to_owned()),
+                None => {},
+            }
+        }
+        let (ours, theirs) = ours(m.scores());
+        lines.push(format!("Match: {} to {}, first to {}", ours, theirs, m.rules().target));
+        let mut actions = Vec::new();
+        if m.determine_winner().is_none() {
+            actions.push(MenuAction::NextHand);
+        }
+        if game.record().is_some() {
+            actions.push(MenuAction::Replay);
+        }
+        actions.extend(&[MenuAction::NewGame, MenuAction::Quit]);
+        GuiMenu::new(title.to_owned(), lines, actions)
+    }
+
+    fn menu_action(&mut self, action: MenuAction) -> bool {
+        self.menu = None;
+        self.paused = false;
+        match action {
+            MenuAction::Resume => {},
+            MenuAction::NextHand => {
+                if let Some(game) = self.finished_game.take() {
+                    self.view = View::Game(game);
+                }
+                if let View::Game(ref mut game) = self.view {
+                    game.next_hand();
+                    game.set_layout(self.layout);
+                }
+            }
+            MenuAction::Replay => return self.replay_finished_game(),
+            MenuAction::NewGame => {
+                let mut game = Game::new(None, None);
+                game.set_layout(self.layout);
+                self.view = View::Game(Box::new(game));
+                self.finished_game = None;
+            }
+            MenuAction::Quit => self.quit = true,
+        }
+        true
+    }
+
+    fn replay_finished_game(&mut self) -> bool {
+        let viewer = match self.view {
+            View::Game(ref game) => match game.record() {
+                Some(record) => ReplayViewer::new(&record),
+                None => return false,
+            },
+            View::Replay(_) => return false,
+        };
+        match viewer {
+            Ok(mut viewer) => {
+                viewer.set_layout(self.layout);
+                // removes the save file of the finished game
+                if let Err(e) = self.save_unfinished_game() {
+                    println!("Error: {}", e);
+                }
+                if let View::Game(game) = mem::replace(&mut self.view, View::Replay(Box::new(viewer))) {
+                    self.finished_game = Some(game);
+                }
+            }
+            Err(e) => println!("Could not replay the game: {}", e),
+        }
+        true
+    }
+
+    pub fn save_unfinished_game(&self) -> Result<(), String> {
+        let hokm = match (&self.saved_game, &self.view) {
+            (Some(hokm), _) => hokm,
+            (None, View::Game(game)) => game.hokm(),
+            (None, View::Replay(_)) => return Ok(()),
+        };
+        if hokm.game_state() == GameState::Finished {
+            if Path::new(SAVE_FILE).exists() {
+                fs::remove_file(SAVE_FILE).map_err(|e| e.to_string())?;
+            }
+            return Ok(());
+        }
+        let file = File::create(SAVE_FILE).map_err(|e| e.to_string())?;
+        hokm.save(BufWriter::new(file)).map_err(|e| e.to_string())
+    }
+}
+
+fn load_saved_game() -> Option<Hokm> {
+    let file = File::open(SAVE_FILE).ok()?;
+    match Hokm::load(BufReader::new(file)) {
+        Ok(hokm) => Some(hokm),
+        Err(e) => {
+            println!("Could not load {}: {}", SAVE_FILE, e);
+            None
+        }
     }
 }
 
 impl Paintable for Scene {
     fn process(&mut self) -> bool {
-        self.game.process()
+        if self.saved_game.is_some() || self.paused {
+            return false;
+        }
+        if let View::Game(ref game) = self.view {
+            if game.is_over() && self.menu.is_none() {
+                let menu = Scene::game_over_menu(game);
+                self.show_menu(menu);
+                return true;
+            }
+        }
+        match self.view {
+            View::Game(ref mut game) => game.process(),
+            View::Replay(ref mut viewer) => viewer.process(),
+        }
     }
 
     fn paint(&mut self, textures: &Textures, canvas: &mut WindowCanvas) -> Result<(), String> {
         canvas.clear();
-        canvas.copy(&textures.background, None, None)?;
-        self.game.paint(textures, canvas)?;
+        canvas.copy(textures.background(), None, None)?;
+        match self.view {
+            View::Game(ref mut game) => game.paint(textures, canvas)?,
+            View::Replay(ref mut viewer) => viewer.paint(textures, canvas)?,
+        }
+        if self.saved_game.is_some() {
+            self.prompt.paint(textures, canvas)?;
+        }
+        if self.paused {
+            let (w, h) = (self.layout.width as i16, self.layout.height as i16);
+            canvas.box_(0, 0, w, h, Color::RGBA(0, 0, 0, 120))?;
+        }
+        if let Some(ref mut menu) = self.menu {
+            menu.paint(textures, canvas)?;
+        }
         canvas.present();
         Ok(())
     }
@@ -291,6 +597,18 @@ impl Paintable for Scene {
 
 impl Clickable for Scene {
     fn click(&mut self, x: i32, y: i32) -> (bool, Option<Card>) {
-        self.game.click(x, y)
+        if self.saved_game.is_some() {
+            return (false, None);
+        }
+        if let Some(ref menu) = self.menu {
+            return match menu.action_at(x, y) {
+                Some(action) => (self.menu_action(action), None),
+                None => (false, None),
+            };
+        }
+        match self.view {
+            View::Game(ref mut game) => game.click(x, y),
+            View::Replay(_) => (false, None),
+        }
     }
 }
@@ -19,6 +19,7 @@ pub struct GuiPile {
     spread: PileSpread,
     gui_cards: Stacked<GuiCard>,
     place_holder: GuiCardPlaceHolder,
+    layout: Layout,
     x: i32,
     y: i32,
     pub name: Option<String>,
@@ -26,12 +27,13 @@ pub struct GuiPile {
 }
 
 impl GuiPile {
-    pub fn new(size: usize, spread: PileSpread) -> Self {
+    pub fn new(size: usize, spread: PileSpread, layout: Layout) -> Self {
         GuiPile {
             size,
             spread,
             gui_cards: Stacked::new(),
             place_holder: GuiCardPlaceHolder::new(),
+            layout,
             x: 0,
             y: 0,
             name: None,
@@ -39,27 +41,27 @@ impl GuiPile {
         }
     }
 
-    fn scale() -> f64 {
-        SMALLER_CARDS
+    fn scale(layout: &Layout) -> f64 {
+        layout.smaller_cards()
     }
-    pub fn width() -> u32 {
-        (CARD_WIDTH as f64 * GuiPile::scale()) as u32
+    pub fn width(layout: &Layout) -> u32 {
+        (CARD_WIDTH as f64 * GuiPile::scale(layout)) as u32
     }
-    pub fn height() -> u32 {
-        (CARD_HEIGHT as f64 * GuiPile::scale()) as u32
+    pub fn height(layout: &Layout) -> u32 {
+        (CARD_HEIGHT as f64 * GuiPile::scale(layout)) as u32
     }
 
     fn arrange_cards(&mut self) {
         self.arranged = true;
         self.gui_cards = Stacked::new();
-        self.place_holder.scale = GuiPile::scale();
+        self.place_holder.scale = GuiPile::scale(&self.layout);
         self.place_holder.set_position(self.x, self.y);
         let (size, sx, sy) = self.pos_info(self.size);
         for i in 0..size {
             let mut gc = GuiCard::new(Card::new(Rank::Ace, Suit::Spades));
             gc.face_up = false;
             gc.set_position(self.x + sx * i as i32, self.y + sy * i as i32);
-            gc.scale = GuiPile::scale();
+            gc.scale = GuiPile::scale(&self.layout);
             self.gui_cards.add(i as u8, gc);
         }
     }
@@ -73,8 +75,8 @@ impl GuiPile {
                 };
                 (y, 1, 1)
             },
-            PileSpread::Horizontal => (x, 3, 0),
-            PileSpread::Vertical => (x, 0, 3),
+            PileSpread::Horizontal => (x, self.layout.scaled(3), 0),
+            PileSpread::Vertical => (x, 0, self.layout.scaled(3)),
         }
     }
 
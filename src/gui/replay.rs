@@ -0,0 +1,153 @@
+
+use super::*;
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+
+// Steps through a recorded game with all four hands face up
+pub struct ReplayViewer {
+    replay: Replay,
+    gui_hands: Vec<GuiHand>,
+    gui_trick: Option<GuiTrick>,
+    played_card: Option<Animated<GuiCard>>,
+    layout: Layout,
+    arranged: bool,
+}
+
+impl ReplayViewer {
+    pub fn new(record: &GameRecord) -> Result<Self, String> {
+        let replay = record.replay().map_err(|e| e.to_string())?;
+        Ok(ReplayViewer {
+            replay,
+            gui_hands: Vec::new(),
+            gui_trick: None,
+            played_card: None,
+            layout: Layout::default(),
+            arranged: false,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let record = GameRecord::read(BufReader::new(file)).map_err(|e| format!("{}: {}", path.display(), e))?;
+        ReplayViewer::new(&record)
+    }
+
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+        self.played_card = None;
+        self.arranged = false;
+    }
+
+    // returns true if the key is handled
+    pub fn key_down(&mut self, keycode: Keycode) -> bool {
+        let result = match keycode {
+            Keycode::Right => {
+                self.forward();
+                return true;
+            }
+            Keycode::Left => self.replay.backward(),
+            Keycode::Home => self.replay.seek(0),
+            Keycode::End => self.replay.seek(usize::MAX),
+            _ => return false,
+        };
+        if let Err(e) = result {
+            println!("Error: {}", e);
+        }
+        self.played_card = None;
+        self.arranged = false;
+        true
+    }
+
+    fn forward(&mut self) {
+        // finish the previous step first
+        if self.played_card.take().is_some() {
+            self.arranged = false;
+        }
+        if !self.arranged {
+            self.arrange_objects();
+        }
+        let event = match self.replay.forward() {
+            Some(Ok(event)) => event,
+            Some(Err(e)) => {
+                println!("Error: {}", e);
+                return;
+            }
+            None => return,
+        };
+        match event {
+            GameEvent::PlayedCard(p, card) => {
+                let gui_trick = self.gui_trick.as_mut().unwrap();
+                let gc = self.gui_hands[p.as_index()].pop_card(card).unwrap();
+                let mut ac = Animated::new(gc);
+                let steps = 15;
+                ac.move_to(gui_trick.position_of(p.as_index()).unwrap(), steps);
+                ac.rotate_to(180.0, steps);
+                ac.scale_card(self.layout.smaller_cards(), steps);
+                self.played_card = Some(ac);
+            }
+            _ => self.arranged = false,
+        }
+    }
+
+    fn arrange_objects(&mut self) {
+        self.arranged = true;
+        let hokm = self.replay.hokm();
+        let layout = self.layout;
+        self.gui_hands = (0..4).map(|i| {
+            let mut gui_hand = GuiHand::new(hokm.player_state(PlayerNumber::from_index(i)).hand().to_owned(), layout);
+            gui_hand.scale = layout.smaller_cards();
+            gui_hand.clickable = false;
+            gui_hand.move_by(0, 8 * layout.height as i32 / 17);
+            // same seats as the trick: player one on the left, four at the bottom
+            gui_hand.set_rotation(90.0 * ((i + 1) % 4) as f64);
+            gui_hand
+        }).collect();
+        self.gui_trick = hokm.trick().map(|trick| GuiTrick::new(trick.to_owned(), layout));
+    }
+}
+
+impl Paintable for ReplayViewer {
+    fn process(&mut self) -> bool {
+        if let Some(ref mut ac) = self.played_card {
+            if !ac.process() {
+                self.played_card = None;
+                self.arranged = false;
+            }
+            return true;
+        }
+        false
+    }
+
+    fn paint(&mut self, textures: &Textures, canvas: &mut WindowCanvas) -> Result<(), String> {
+        if !self.arranged {
+            self.arrange_objects();
+        }
+        for gui_hand in self.gui_hands.iter_mut() {
+            gui_hand.paint(textures, canvas)?;
+        }
+        if let Some(ref mut gui_trick) = self.gui_trick {
+            gui_trick.paint(textures, canvas)?;
+        }
+        if let Some(ref mut ac) = self.played_card {
+            ac.paint(textures, canvas)?;
+        }
+        let hokm = self.replay.hokm();
+        if let Some(ts) = hokm.trump_suit() {
+            let (t, src) = textures.suit(ts);
+            let (pos, size) = (self.layout.scaled(10), self.layout.scaled(30) as u32);
+            canvas.copy(t, src, Rect::new(pos, pos, size, size))?;
+        }
+        let white = Color::RGB(255, 255, 255);
+        let scaled = |length| self.layout.scaled(length) as i16;
+        let bottom = self.layout.height as i16;
+        let scores = hokm.team_scores();
+        canvas.string(scaled(60), scaled(25), &format!("{} - {}", scores.0, scores.1), white)?;
+        let hakem = format!("Hakem: player {}", hokm.trump_caller() as usize);
+        canvas.string(scaled(10), scaled(50), &hakem, white)?;
+        let step = format!("Step {}", self.replay.position());
+        canvas.string(scaled(10), bottom - scaled(40), &step, white)?;
+        let help = "Left/Right: step, Home/End: start/end";
+        canvas.string(scaled(10), bottom - scaled(20), help, white)?;
+        Ok(())
+    }
+}
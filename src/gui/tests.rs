@@ -0,0 +1,32 @@
+
+use super::*;
+
+#[test]
+fn bundled_theme() {
+    let theme = Theme::load(Path::new(DEFAULT_THEME)).unwrap();
+    let card = |s: &str| s.parse::<Card>().unwrap();
+    assert_eq!(theme.front(card("AS")), Rect::new(0, 708, 226, 316));
+    assert_eq!(theme.front(card("TH")), Rect::new(9 * 265, 354, 226, 316));
+    assert_eq!(theme.front(card("KD")), Rect::new(12 * 265, 3 * 354, 226, 316));
+    assert_eq!(theme.suit(Suit::Diamonds), Rect::new(120, 0, 60, 60));
+    assert_eq!(theme.backs[0], Path::new("res/back-3.png"));
+    for path in theme.backs.iter().chain(&theme.backgrounds).chain(vec![&theme.fronts.path, &theme.suits.path]) {
+        assert!(path.exists(), "{}", path.display());
+    }
+}
+
+#[test]
+fn theme_errors() {
+    let text = fs::read_to_string(DEFAULT_THEME).unwrap();
+    let read = |text: &str| Theme::read(text.as_bytes(), Path::new("res"));
+    assert!(read(&text).is_ok());
+    // a user deck with its suits in another order
+    let theme = read(&text.replace("rows C H S D", "rows S H D C")).unwrap();
+    assert_eq!(theme.front("2C".parse().unwrap()), Rect::new(265, 3 * 354, 226, 316));
+    assert!(read(&text.replace("rows C H S D", "rows C H S S")).is_err());
+    assert!(read(&text.replace("columns A 2", "columns A A")).is_err());
+    assert!(read(&text.replace("226 316 265 354", "226 316 265")).is_err());
+    assert!(read(&text.replace("fronts", "front")).is_err());
+    let no_backs: String = text.lines().filter(|l| !l.starts_with("back ")).map(|l| format!("{}\n", l)).collect();
+    assert!(read(&no_backs).is_err());
+}
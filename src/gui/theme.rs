@@ -0,0 +1,192 @@
+
+use super::*;
+use sdl2::image::LoadSurface;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::BlendMode;
+use sdl2::surface::Surface;
+use std::io::BufRead;
+
+// The bundled theme, see res/theme.txt for the format
+pub const DEFAULT_THEME: &str = "res/theme.txt";
+
+// Sprites of the same size, laid out in rows and columns
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpriteSheet {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    // from one sprite to the next
+    pub step_x: u32,
+    pub step_y: u32,
+}
+
+impl SpriteSheet {
+    pub fn sprite(&self, column: usize, row: usize) -> Rect {
+        let (x, y) = (column as u32 * self.step_x, row as u32 * self.step_y);
+        Rect::new(x as i32, y as i32, self.width, self.height)
+    }
+}
+
+// The images of a deck of cards and of the table, listed in a manifest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub fronts: SpriteSheet,
+    // the suit of each row and the rank of each column of the fronts
+    pub rows: Vec<Suit>,
+    pub columns: Vec<Rank>,
+    // suit icons in a single row
+    pub suits: SpriteSheet,
+    pub suit_columns: Vec<Suit>,
+    pub backs: Vec<PathBuf>,
+    pub backgrounds: Vec<PathBuf>,
+}
+
+impl Theme {
+    pub fn load(path: &Path) -> Result<Theme, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Theme::read(BufReader::new(file), dir).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // Files are relative to `dir`, usually the directory of the manifest
+    pub fn read<R: BufRead>(r: R, dir: &Path) -> Result<Theme, String> {
+        let (mut fronts, mut rows, mut columns) = (None, None, None);
+        let (mut suits, mut suit_columns) = (None, None);
+        let (mut backs, mut backgrounds) = (Vec::new(), Vec::new());
+        for (i, line) in r.lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            let invalid = |msg: &str| format!("line {}: {}: {}", i + 1, msg, line);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                [] => {},
+                [first, ..] if first.starts_with('#') => {},
+                ["fronts", ref sheet @ ..] => fronts = Some(sprite_sheet(dir, sheet).ok_or_else(|| invalid("invalid sprite sheet"))?),
+                ["suits", ref sheet @ ..] => suits = Some(sprite_sheet(dir, sheet).ok_or_else(|| invalid("invalid sprite sheet"))?),
+                ["rows", ref s @ ..] => rows = Some(all_suits(s).ok_or_else(|| invalid("expected each suit once"))?),
+                ["suit-columns", ref s @ ..] => suit_columns = Some(all_suits(s).ok_or_else(|| invalid("expected each suit once"))?),
+                ["columns", ref r @ ..] => columns = Some(all_ranks(r).ok_or_else(|| invalid("expected each rank once"))?),
+                ["back", file] => backs.push(dir.join(file)),
+                ["background", file] => backgrounds.push(dir.join(file)),
+                _ => return Err(invalid("unexpected line")),
+            }
+        }
+        let missing = |what: &str| format!("missing {}", what);
+        if backs.is_empty() || backgrounds.is_empty() {
+            return Err(missing("back or background"));
+        }
+        Ok(Theme {
+            fronts: fronts.ok_or_else(|| missing("fronts"))?,
+            rows: rows.ok_or_else(|| missing("rows"))?,
+            columns: columns.ok_or_else(|| missing("columns"))?,
+            suits: suits.ok_or_else(|| missing("suits"))?,
+            suit_columns: suit_columns.ok_or_else(|| missing("suit-columns"))?,
+            backs,
+            backgrounds,
+        })
+    }
+
+    pub fn front(&self, card: Card) -> Rect {
+        let column = self.columns.iter().position(|r| *r == card.rank()).expect("every rank has a column");
+        let row = self.rows.iter().position(|s| *s == card.suit()).expect("every suit has a row");
+        self.fronts.sprite(column, row)
+    }
+
+    pub fn suit(&self, suit: Suit) -> Rect {
+        let column = self.suit_columns.iter().position(|s| *s == suit).expect("every suit has a column");
+        self.suits.sprite(column, 0)
+    }
+
+    // The parts of the sprite sheets that change color in a four-color deck
+    fn four_color_fronts(&self) -> Vec<(Rect, Suit)> {
+        let width = self.fronts.step_x * (self.columns.len() as u32 - 1) + self.fronts.width;
+        self.rows.iter().enumerate()
+            .map(|(row, &suit)| (Rect::new(0, (row as u32 * self.fronts.step_y) as i32, width, self.fronts.height), suit))
+            .collect()
+    }
+    fn four_color_suits(&self) -> Vec<(Rect, Suit)> {
+        self.suit_columns.iter().map(|&suit| (self.suit(suit), suit)).collect()
+    }
+
+    pub fn load_fronts<'a, T>(&self, creator: &'a TextureCreator<T>, four_color: bool) -> Result<Texture<'a>, String> {
+        match four_color {
+            true => load_recolored(creator, &self.fronts.path, &self.four_color_fronts()),
+            false => creator.load_texture(&self.fronts.path),
+        }
+    }
+
+    pub fn load_suits<'a, T>(&self, creator: &'a TextureCreator<T>, four_color: bool) -> Result<Texture<'a>, String> {
+        match four_color {
+            true => load_recolored(creator, &self.suits.path, &self.four_color_suits()),
+            false => creator.load_texture(&self.suits.path),
+        }
+    }
+}
+
+// file width height step_x step_y
+fn sprite_sheet(dir: &Path, words: &[&str]) -> Option<SpriteSheet> {
+    match *words {
+        [file, width, height, step_x, step_y] => Some(SpriteSheet {
+            path: dir.join(file),
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+            step_x: step_x.parse().ok()?,
+            step_y: step_y.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+fn all_suits(words: &[&str]) -> Option<Vec<Suit>> {
+    let suits = words.iter().map(|w| w.parse().ok()).collect::<Option<Vec<Suit>>>()?;
+    match suits.len() == 4 && Suit::all_suits().iter().all(|s| suits.contains(s)) {
+        true => Some(suits),
+        false => None,
+    }
+}
+
+fn all_ranks(words: &[&str]) -> Option<Vec<Rank>> {
+    let ranks = words.iter().map(|w| w.parse().ok()).collect::<Option<Vec<Rank>>>()?;
+    let mut sorted = ranks.clone();
+    sorted.sort();
+    sorted.dedup();
+    match sorted.len() == 13 && ranks.len() == 13 {
+        true => Some(ranks),
+        false => None,
+    }
+}
+
+// Clubs turn green and diamonds blue, so that every suit has its own color
+fn load_recolored<'a, T>(creator: &'a TextureCreator<T>, path: &Path, regions: &[(Rect, Suit)]) -> Result<Texture<'a>, String> {
+    let mut image = Surface::from_file(path)?;
+    image.set_blend_mode(BlendMode::None)?;
+    // a known layout of the pixels: red, green, blue, alpha
+    let mut surface = Surface::new(image.width(), image.height(), PixelFormatEnum::RGBA32)?;
+    image.blit(None, &mut surface, None)?;
+    let (width, height, pitch) = (surface.width() as i32, surface.height() as i32, surface.pitch() as usize);
+    surface.with_lock_mut(|pixels| {
+        for &(rect, suit) in regions {
+            for y in rect.top().max(0)..rect.bottom().min(height) {
+                for x in rect.left().max(0)..rect.right().min(width) {
+                    let i = y as usize * pitch + x as usize * 4;
+                    recolor(&mut pixels[i..i + 3], suit);
+                }
+            }
+        }
+    });
+    creator.create_texture_from_surface(&surface).map_err(|e| e.to_string())
+}
+
+fn recolor(rgb: &mut [u8], suit: Suit) {
+    let (r, g, b) = (rgb[0] as i32, rgb[1] as i32, rgb[2] as i32);
+    match suit {
+        // red ink
+        Suit::Diamonds if r > g + 60 && r > b + 60 => rgb.swap(0, 2),
+        // black ink
+        Suit::Clubs if r.max(g).max(b) < 100 => {
+            rgb[0] = (r / 2) as u8;
+            rgb[1] = (100 + g / 2) as u8;
+            rgb[2] = (b / 2) as u8;
+        }
+        _ => {},
+    }
+}
@@ -5,16 +5,18 @@ use super::*;
 pub struct GuiTrick {
     trick: Trick,
     cards: Stacked<GuiCardOrPlaceHolder>,
+    layout: Layout,
     tx: i32,
     ty: i32,
     arranged: bool,
 }
 
 impl GuiTrick {
-    pub fn new(trick: Trick) -> Self {
+    pub fn new(trick: Trick, layout: Layout) -> Self {
         GuiTrick {
             trick,
             cards: Stacked::new(),
+            layout,
             tx: 0,
             ty: 0,
             arranged: false,
@@ -24,14 +26,14 @@ impl GuiTrick {
     fn arrange_cards(&mut self) {
         self.arranged = true;
         self.cards = Stacked::new();
-        let scale = SMALLER_CARDS;
+        let scale = self.layout.smaller_cards();
         let scale_spaced = scale * 1.1;
         for i in 0..4 {
             let ii = i as isize;
             let xf = ((ii % 2 - 1) * (ii - 1) * 2) as i32 + 1;
             let yf = ((ii % 2) * (1 - 2 * (ii/3))) as i32 + 1;
-            let x = (SCENE_WIDTH as i32 - xf * (CARD_WIDTH as f64 * scale_spaced) as i32) / 2;
-            let y = (SCENE_HEIGHT as i32 - yf * (CARD_HEIGHT as f64 * scale_spaced) as i32) / 2;
+            let x = (self.layout.width as i32 - xf * (CARD_WIDTH as f64 * scale_spaced) as i32) / 2;
+            let y = (self.layout.height as i32 - yf * (CARD_HEIGHT as f64 * scale_spaced) as i32) / 2;
             let gop = match self.trick.played_cards[i] {
                 Some(card) => {
                     let mut gc = GuiCard::new(card);
@@ -1,8 +1,44 @@
 
-use hokm::gui::gui_main;
+use hokm::game::PlayerNumber;
+use hokm::gui::{gui_main, GuiOptions};
+use std::env;
+
+fn parse_args() -> Result<GuiOptions, String> {
+    let mut options = GuiOptions::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                let seed = args.next().ok_or("--seed requires a value")?;
+                let seed = seed.parse().map_err(|_| format!("invalid seed: {}", seed))?;
+                options.seed = Some(seed);
+            }
+            "--hakem" => {
+                let hakem = args.next().ok_or("--hakem requires a player number")?;
+                options.hakem = match hakem.parse::<usize>() {
+                    Ok(n @ 1..=4) => Some(PlayerNumber::from(n)),
+                    _ => return Err(format!("invalid player number: {}", hakem)),
+                };
+            }
+            "--fullscreen" => options.fullscreen = true,
+            "--four-color" => options.four_color = true,
+            "--theme" => {
+                let path = args.next().ok_or("--theme requires a file")?;
+                options.theme = Some(path.into());
+            }
+            "--replay" => {
+                let path = args.next().ok_or("--replay requires a file")?;
+                options.replay = Some(path.into());
+            }
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+    Ok(options)
+}
 
 pub fn main() {
-    if let Err(e) = gui_main() {
+    let result = parse_args().and_then(gui_main);
+    if let Err(e) = result {
         println!("Error: {}", e);
     }
 }
@@ -0,0 +1,196 @@
+
+use crate::game::*;
+use crate::cards::*;
+use super::position::*;
+use super::SensiblePlayer;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+// Balances trying moves that look good against trying the others
+const EXPLORATION: f64 = 0.7;
+
+// How long a player may think about a move
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchBudget {
+    Iterations(u32),
+    Time(Duration),
+}
+
+impl SearchBudget {
+    fn exhausted(&self, iterations: u32, start: Instant) -> bool {
+        match *self {
+            SearchBudget::Iterations(n) => iterations >= n,
+            // at least one iteration, to have a move
+            SearchBudget::Time(t) => iterations > 0 && start.elapsed() >= t,
+        }
+    }
+}
+
+// Information set Monte Carlo tree search: every iteration deals the unseen
+// cards in a way that fits what the player has seen, and walks down a tree
+// of the moves that are legal in that deal. The tree is shared by all the
+// deals, so moves are judged by how they do across them.
+pub struct IsmctsPlayer {
+    budget: SearchBudget,
+    rng: RefCell<StdRng>,
+}
+
+// A move in the search tree
+struct Node {
+    card: Option<Card>,
+    // who played the card, the rewards are for their team
+    player: PlayerNumber,
+    children: Vec<usize>,
+    visits: u32,
+    // the number of times the card could be played when its parent was
+    // visited
+    availability: u32,
+    reward: f64,
+}
+
+impl Node {
+    fn new(card: Option<Card>, player: PlayerNumber) -> Self {
+        Node {
+            card,
+            player,
+            children: Vec::new(),
+            visits: 0,
+            availability: 1,
+            reward: 0.0,
+        }
+    }
+
+    fn ucb(&self) -> f64 {
+        let visits = self.visits as f64;
+        self.reward / visits + EXPLORATION * ((self.availability as f64).ln() / visits).sqrt()
+    }
+}
+
+impl IsmctsPlayer {
+    pub fn new(budget: SearchBudget) -> Self {
+        IsmctsPlayer::with_seed(budget, thread_rng().gen())
+    }
+    pub fn with_seed(budget: SearchBudget, seed: u64) -> Self {
+        IsmctsPlayer {
+            budget,
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    fn choose_card(&self, view: &PlayerView) -> Card {
+        let moves = view.legal_moves();
+        if moves.len() == 1 {
+            return moves[0];
+        }
+        let rng = &mut *self.rng.borrow_mut();
+        let mut tree = vec![Node::new(None, view.seat())];
+        let start = Instant::now();
+        let mut iterations = 0;
+        let deals = Deals::new(view);
+        while !self.budget.exhausted(iterations, start) {
+            let mut pos = deals.deal(rng);
+            let path = select_and_expand(&mut tree, &mut pos, rng);
+            while pos.winner().is_none() {
+                let card = playout_move(&pos, rng);
+                pos.play(card);
+            }
+            for n in path {
+                tree[n].visits += 1;
+                tree[n].reward += reward(&pos, tree[n].player.team());
+            }
+            iterations += 1;
+        }
+        let best = tree[0].children.iter().max_by_key(|&&c| tree[c].visits).expect("a move was tried");
+        tree[*best].card.unwrap()
+    }
+}
+
+// Walks down the tree as far as every legal move has been tried, then adds
+// one of the untried moves. Returns the nodes visited, with the position
+// after their moves.
+fn select_and_expand<R: Rng>(tree: &mut Vec<Node>, pos: &mut Position, rng: &mut R) -> Vec<usize> {
+    let mut node = 0;
+    let mut path = vec![node];
+    while pos.winner().is_none() {
+        let moves = pos.legal_moves();
+        let mut untried = moves;
+        for &c in &tree[node].children {
+            untried.remove(tree[c].card.unwrap());
+        }
+        if !untried.is_empty() {
+            let card = untried.iter().nth(rng.gen_range(0, untried.len())).unwrap();
+            let child = tree.len();
+            tree.push(Node::new(Some(card), pos.turn()));
+            tree[node].children.push(child);
+            pos.play(card);
+            path.push(child);
+            break;
+        }
+        let mut best: Option<(usize, f64)> = None;
+        for i in 0..tree[node].children.len() {
+            let c = tree[node].children[i];
+            if !moves.contains(tree[c].card.unwrap()) {
+                continue;
+            }
+            tree[c].availability += 1;
+            let ucb = tree[c].ucb();
+            if best.map_or(true, |(_, b)| ucb > b) {
+                best = Some((c, ucb));
+            }
+        }
+        node = best.expect("a legal move").0;
+        pos.play(tree[node].card.unwrap());
+        path.push(node);
+    }
+    path
+}
+
+// A quick guess at a good move to finish the hand with: win the trick as
+// cheaply as possible unless the partner is winning it, otherwise throw the
+// lowest card away. Leads are random.
+fn playout_move<R: Rng>(pos: &Position, rng: &mut R) -> Card {
+    let moves = pos.legal_moves();
+    let (winner, winning) = match pos.winning_card() {
+        Some(w) => w,
+        None => return moves.iter().nth(rng.gen_range(0, moves.len())).unwrap(),
+    };
+    let trump = pos.trump_suit();
+    // trumps are worth more than the other suits
+    let value = |c: Card| (c.suit() == trump, c.rank());
+    let lowest = |cards: CardSet| cards.iter().min_by_key(|&c| value(c));
+    if winner.team() != pos.turn().team() {
+        let beating: CardSet = moves.iter().filter(|&c| pos.beats(c, winning)).collect();
+        if let Some(card) = lowest(beating) {
+            return card;
+        }
+    }
+    lowest(moves).unwrap()
+}
+
+// Winning the hand counts most, the tricks break ties between wins and
+// between losses
+fn reward(pos: &Position, team: Team) -> f64 {
+    let won = match pos.winner() == Some(team) {
+        true => 1.0,
+        false => 0.0,
+    };
+    0.9 * won + 0.1 * pos.tricks_won_by(team) as f64 / 7.0
+}
+
+impl Player for IsmctsPlayer {
+    fn name(&self) -> String {
+        "ISMCTS".to_owned()
+    }
+
+    // Five cards say too little to search, so the trump suit is chosen
+    // like `SensiblePlayer` does
+    fn call_trump_suit(&self, view: &PlayerView) -> Decision<Suit> {
+        SensiblePlayer::new().call_trump_suit(view)
+    }
+
+    fn play(&self, view: &PlayerView) -> Decision<Card> {
+        Decision::Ready(self.choose_card(view))
+    }
+}
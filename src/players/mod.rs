@@ -1,8 +1,16 @@
 
+mod ismcts;
+mod pimc;
+mod position;
 mod random;
 mod sensible;
+mod solver;
 #[cfg(test)]
 mod tests;
 
+pub use ismcts::{IsmctsPlayer, SearchBudget};
+pub use pimc::PimcPlayer;
+pub use position::Position;
 pub use random::RandomPlayer;
 pub use sensible::SensiblePlayer;
+pub use solver::Solver;
@@ -0,0 +1,91 @@
+
+use crate::game::*;
+use crate::cards::*;
+use super::position::*;
+use super::solver::Solver;
+use super::SensiblePlayer;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use std::cell::RefCell;
+use std::cmp::Reverse;
+
+// The tricks that win the hand
+const WON: u32 = 7;
+
+// Perfect information Monte Carlo: deals the unseen cards a number of times
+// in ways that fit what the player has seen, solves every deal as if all the
+// hands were known, and plays the card that does best on average. Only the
+// tricks around the 7 that win the hand are told apart, which is much less
+// to search than solving the deals.
+pub struct PimcPlayer {
+    samples: u32,
+    rng: RefCell<StdRng>,
+}
+
+impl PimcPlayer {
+    pub fn new(samples: u32) -> Self {
+        PimcPlayer::with_seed(samples, thread_rng().gen())
+    }
+    pub fn with_seed(samples: u32, seed: u64) -> Self {
+        PimcPlayer {
+            samples,
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    fn choose_card(&self, view: &PlayerView) -> Card {
+        let moves = view.legal_moves();
+        if moves.len() == 1 {
+            return moves[0];
+        }
+        let rng = &mut *self.rng.borrow_mut();
+        let deals = Deals::new(view);
+        // the moves of a deal lead to many of the same positions
+        let mut solver = Solver::new();
+        let mut rewards = vec![0.0; moves.len()];
+        for _ in 0..self.samples {
+            let pos = deals.deal(rng);
+            for (card, tricks) in solver.evaluate_moves_between(&pos, WON - 1, WON + 1) {
+                let i = moves.iter().position(|&c| c == card).expect("a legal move");
+                rewards[i] += reward(tricks);
+            }
+        }
+        // ties go to the card `SensiblePlayer` would play, then to the
+        // lowest card
+        let sensible = SensiblePlayer::new().play(view).ready();
+        let preferred = |i: usize| (Some(moves[i]) == sensible, Reverse(moves[i].rank()));
+        let mut best = 0;
+        for i in 1..moves.len() {
+            if rewards[i] > rewards[best] || rewards[i] == rewards[best] && preferred(i) > preferred(best) {
+                best = i;
+            }
+        }
+        moves[best]
+    }
+}
+
+// Winning the hand counts most, a trick to spare or a trick short breaks
+// ties between wins and between losses
+fn reward(tricks: u32) -> f64 {
+    let won = match tricks >= WON {
+        true => 1.0,
+        false => 0.0,
+    };
+    0.9 * won + 0.1 * (tricks + 1 - WON) as f64 / 2.0
+}
+
+impl Player for PimcPlayer {
+    fn name(&self) -> String {
+        "PIMC".to_owned()
+    }
+
+    // The trump suit is chosen like `SensiblePlayer` does, see
+    // `IsmctsPlayer`
+    fn call_trump_suit(&self, view: &PlayerView) -> Decision<Suit> {
+        SensiblePlayer::new().call_trump_suit(view)
+    }
+
+    fn play(&self, view: &PlayerView) -> Decision<Card> {
+        Decision::Ready(self.choose_card(view))
+    }
+}
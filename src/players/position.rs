@@ -0,0 +1,327 @@
+
+use crate::game::*;
+use crate::cards::*;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+
+// The state of a hand in play with every player's cards known, e.g. one of
+// the deals a player thinks possible. Cheap to copy, so searches copy it
+// instead of taking moves back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    hands: [CardSet; 4],
+    trump_suit: Suit,
+    leader: PlayerNumber,
+    turn: PlayerNumber,
+    // the current trick by player
+    trick: [Option<Card>; 4],
+    // tricks won by each team
+    tricks: (u32, u32),
+}
+
+impl Position {
+    // The position of the player's view with the other players holding
+    // `hands`, the player's own hand is taken from the view.
+    pub fn new(view: &PlayerView, mut hands: [CardSet; 4]) -> Self {
+        let trick = view.trick().expect("playing w/o trick?!");
+        hands[view.seat().as_index()] = view.hand().to_set();
+        Position {
+            hands,
+            trump_suit: view.trump_suit().expect("playing w/o trump suit?!"),
+            leader: trick.first_player,
+            turn: view.turn(),
+            trick: trick.played_cards,
+            tricks: view.team_scores(),
+        }
+    }
+
+    // The remaining hands of the players, the trick being played and the
+    // tricks each team has won
+    pub fn with_hands(hands: &[Hand; 4], trump_suit: Suit, trick: &Trick, tricks: (u32, u32)) -> Self {
+        let played = trick.played_cards.iter().filter(|c| c.is_some()).count();
+        Position {
+            hands: [hands[0].to_set(), hands[1].to_set(), hands[2].to_set(), hands[3].to_set()],
+            trump_suit,
+            leader: trick.first_player,
+            turn: PlayerNumber::from_index((trick.first_player.as_index() + played) % 4),
+            trick: trick.played_cards,
+            tricks,
+        }
+    }
+
+    // The position of a game in play, None before the trump suit is set
+    pub fn of_game(hokm: &Hokm) -> Option<Self> {
+        let hands = [1, 2, 3, 4].map(|p| hokm.player_state(p).hand().clone());
+        Some(Position::with_hands(&hands, hokm.trump_suit()?, hokm.trick()?, hokm.team_scores()))
+    }
+
+    pub fn hand(&self, player: PlayerNumber) -> CardSet { self.hands[player.as_index()] }
+    pub fn trump_suit(&self) -> Suit                    { self.trump_suit }
+    pub fn turn(&self) -> PlayerNumber                  { self.turn }
+    pub fn leader(&self) -> PlayerNumber                { self.leader }
+    pub fn trick(&self) -> [Option<Card>; 4]            { self.trick }
+    pub fn tricks(&self) -> (u32, u32)                  { self.tricks }
+
+    pub fn tricks_won_by(&self, team: Team) -> u32 {
+        match team {
+            Team::PlayersOneAndThree => self.tricks.0,
+            Team::PlayersTwoAndFour => self.tricks.1,
+        }
+    }
+
+    pub fn led_suit(&self) -> Option<Suit> {
+        self.trick[self.leader.as_index()].map(|c| c.suit())
+    }
+
+    // The hand is over when a team has won 7 tricks
+    pub fn winner(&self) -> Option<Team> {
+        match self.tricks {
+            (t13, _) if t13 >= 7 => Some(Team::PlayersOneAndThree),
+            (_, t24) if t24 >= 7 => Some(Team::PlayersTwoAndFour),
+            _ => None,
+        }
+    }
+
+    // The player whose card wins the trick so far, and the card
+    pub fn winning_card(&self) -> Option<(PlayerNumber, Card)> {
+        let mut best: Option<(PlayerNumber, Card)> = None;
+        let mut p = self.leader;
+        while let Some(card) = self.trick[p.as_index()] {
+            if best.map_or(true, |(_, b)| self.beats(card, b)) {
+                best = Some((p, card));
+            }
+            p.increment();
+            if p == self.leader {
+                break;
+            }
+        }
+        best
+    }
+
+    // Whether `card` beats `other`, one of the cards of the trick
+    pub fn beats(&self, card: Card, other: Card) -> bool {
+        match (card.suit(), other.suit()) {
+            (s, o) if s == o => card.rank() > other.rank(),
+            (s, _) => s == self.trump_suit,
+        }
+    }
+
+    // The cards the player to move may play, see `legal_moves`
+    pub fn legal_moves(&self) -> CardSet {
+        let hand = self.hand(self.turn);
+        match self.led_suit() {
+            Some(suit) if hand.count_of_suit(suit) > 0 => hand.suit(suit),
+            _ => hand,
+        }
+    }
+
+    // Plays a legal card for the player to move, and completes the trick
+    // after the fourth card
+    pub fn play(&mut self, card: Card) {
+        let p = self.turn.as_index();
+        debug_assert!(self.legal_moves().contains(card), "illegal move: {:?}", card);
+        self.hands[p].remove(card);
+        self.trick[p] = Some(card);
+        self.turn.increment();
+        if self.turn != self.leader {
+            return;
+        }
+        let trick = Trick { played_cards: self.trick, first_player: self.leader };
+        let winner = trick.winner(self.trump_suit).expect("everyone has played");
+        match winner.team() {
+            Team::PlayersOneAndThree => self.tricks.0 += 1,
+            Team::PlayersTwoAndFour => self.tricks.1 += 1,
+        }
+        self.trick = [None; 4];
+        self.leader = winner;
+        self.turn = winner;
+    }
+}
+
+// The suits each player is known to be out of, because they did not follow
+// suit
+fn known_voids(view: &PlayerView) -> [CardSet; 4] {
+    let mut voids = [CardSet::new(); 4];
+    let tricks = view.completed_tricks().iter().map(|t| t.to_trick());
+    for trick in tricks.chain(view.trick().cloned()) {
+        let led = match trick.first_card() {
+            Some(card) => card.suit(),
+            None => continue,
+        };
+        for (i, card) in trick.played_cards.iter().enumerate() {
+            if card.map_or(false, |c| c.suit() != led) {
+                voids[i] |= CardSet::of_suit(led);
+            }
+        }
+    }
+    voids
+}
+
+// Deals the cards a player has not seen to the other players at random, so
+// that everyone has as many cards as they do in the game and no one gets a
+// suit they are known to be out of. The cards of suits the same players may
+// hold are dealt as one group: a deal is how many cards of each group every
+// player gets, and then which ones. The numbers are picked in proportion to
+// the deals they leave, which makes every deal that fits the voids equally
+// likely.
+pub struct Deals {
+    position: Position,
+    // the unseen cards of each group and who may hold them
+    groups: Vec<(Vec<Card>, [bool; 4])>,
+    sizes: [usize; 4],
+    // the number of ways to deal the groups from the first one on by the
+    // places left in the hands
+    counts: HashMap<(usize, [usize; 4]), f64>,
+}
+
+impl Deals {
+    pub fn new(view: &PlayerView) -> Self {
+        let seat = view.seat();
+        let mut sizes = [0; 4];
+        for (i, size) in sizes.iter_mut().enumerate() {
+            let p = PlayerNumber::from_index(i);
+            if p != seat {
+                *size = view.hand_size(p);
+            }
+        }
+        let voids = known_voids(view);
+        let unseen = view.unseen_cards();
+        let mut groups: Vec<(Vec<Card>, [bool; 4])> = Vec::new();
+        for &suit in Suit::all_suits() {
+            let cards = unseen.suit(suit);
+            if cards.is_empty() {
+                continue;
+            }
+            let mut may_hold = [false; 4];
+            for (i, may) in may_hold.iter_mut().enumerate() {
+                *may = sizes[i] > 0 && voids[i].count_of_suit(suit) == 0;
+            }
+            match groups.iter_mut().find(|(_, m)| *m == may_hold) {
+                Some((group, _)) => group.extend(cards.iter()),
+                None => groups.push((cards.iter().collect(), may_hold)),
+            }
+        }
+        let mut deals = Deals {
+            position: Position::new(view, [CardSet::new(); 4]),
+            groups,
+            sizes,
+            counts: HashMap::new(),
+        };
+        let count = deals.count_deals(0, sizes);
+        assert!(count > 0.0, "no deal fits the voids?!");
+        deals
+    }
+
+    // One of the positions the player may be in
+    pub fn deal<R: Rng>(&self, rng: &mut R) -> Position {
+        let mut position = self.position;
+        let mut hands = [CardSet::new(); 4];
+        let mut free = self.sizes;
+        for (g, (cards, _)) in self.groups.iter().enumerate() {
+            let splits = self.splits(g, free);
+            let weights: Vec<f64> = splits
+                .iter()
+                .map(|split| multinomial(split) * self.count(g + 1, left(free, split)))
+                .collect();
+            let mut r = rng.gen::<f64>() * weights.iter().sum::<f64>();
+            // rounding may leave a little of r, which goes to the last split
+            // that has deals left
+            let mut chosen = weights.iter().rposition(|&w| w > 0.0).expect("no split of a group?!");
+            for (k, &w) in weights.iter().enumerate() {
+                if r < w {
+                    chosen = k;
+                    break;
+                }
+                r -= w;
+            }
+            let split = splits[chosen];
+            let mut cards = cards.clone();
+            cards.shuffle(rng);
+            let mut cards = cards.into_iter();
+            for (i, hand) in hands.iter_mut().enumerate() {
+                hand.extend(cards.by_ref().take(split[i]));
+            }
+            free = left(free, &split);
+        }
+        for (i, hand) in hands.iter().enumerate() {
+            if self.sizes[i] > 0 {
+                position.hands[i] = *hand;
+            }
+        }
+        position
+    }
+
+    // The number of ways to deal the cards of groups `g` and up with `free`
+    // places left in the hands
+    fn count_deals(&mut self, g: usize, free: [usize; 4]) -> f64 {
+        if g == self.groups.len() {
+            return self.count(g, free);
+        }
+        if let Some(&count) = self.counts.get(&(g, free)) {
+            return count;
+        }
+        let mut count = 0.0;
+        for split in self.splits(g, free) {
+            count += multinomial(&split) * self.count_deals(g + 1, left(free, &split));
+        }
+        self.counts.insert((g, free), count);
+        count
+    }
+
+    // Like `count_deals`, for the counts it has already found
+    fn count(&self, g: usize, free: [usize; 4]) -> f64 {
+        match g == self.groups.len() {
+            true => match free == [0; 4] {
+                true => 1.0,
+                false => 0.0,
+            },
+            false => self.counts.get(&(g, free)).copied().unwrap_or(0.0),
+        }
+    }
+
+    // The ways the cards of group `g` may be split among the players with
+    // `free` places left, as the number of cards each one gets. The last
+    // group fills the places that are left.
+    fn splits(&self, g: usize, free: [usize; 4]) -> Vec<[usize; 4]> {
+        let (cards, may_hold) = &self.groups[g];
+        let most = |i: usize| match may_hold[i] {
+            true => free[i],
+            false => 0,
+        };
+        let total = cards.len();
+        if g + 1 == self.groups.len() {
+            return match (0..4).all(|i| free[i] <= most(i)) && free.iter().sum::<usize>() == total {
+                true => vec![free],
+                false => Vec::new(),
+            };
+        }
+        let mut splits = Vec::new();
+        for a in 0..=most(0).min(total) {
+            for b in 0..=most(1).min(total - a) {
+                for c in 0..=most(2).min(total - a - b) {
+                    let d = total - a - b - c;
+                    if d <= most(3) {
+                        splits.push([a, b, c, d]);
+                    }
+                }
+            }
+        }
+        splits
+    }
+}
+
+// The places left in the hands after they get `split`
+fn left(free: [usize; 4], split: &[usize; 4]) -> [usize; 4] {
+    let mut left = free;
+    for (l, n) in left.iter_mut().zip(split) {
+        *l -= n;
+    }
+    left
+}
+
+// The number of ways to give the cards of a suit out as in `split`
+fn multinomial(split: &[usize; 4]) -> f64 {
+    let factorial = |n: usize| (1..=n).map(|k| k as f64).product::<f64>();
+    factorial(split.iter().sum()) / split.iter().map(|&n| factorial(n)).product::<f64>()
+}
@@ -1,26 +1,62 @@
 
 use crate::game::*;
 use crate::cards::*;
-use rand::{thread_rng, RngCore};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, RngCore, SeedableRng};
+use std::cell::RefCell;
 
-pub struct RandomPlayer;
+pub struct RandomPlayer {
+    rng: RefCell<StdRng>,
+}
+
+impl RandomPlayer {
+    pub fn new() -> Self {
+        RandomPlayer::with_seed(thread_rng().gen())
+    }
+    pub fn with_seed(seed: u64) -> Self {
+        RandomPlayer::with_rng(StdRng::seed_from_u64(seed))
+    }
+    pub fn with_rng(rng: StdRng) -> Self {
+        RandomPlayer {
+            rng: RefCell::new(rng),
+        }
+    }
+    fn random_index(&self, len: usize) -> usize {
+        self.rng.borrow_mut().next_u32() as usize % len
+    }
+}
+
+impl Default for RandomPlayer {
+    fn default() -> Self {
+        RandomPlayer::new()
+    }
+}
 
 impl Player for RandomPlayer {
     fn name(&self) -> String {
         "Random".to_owned()
     }
 
-    fn call_trump_suit(&self, _hand: &Hand) -> Suit {
-        Suit::Hearts
+    fn call_trump_suit(&self, _view: &PlayerView) -> Decision<Suit> {
+        Decision::Ready(Suit::Hearts)
     }
 
-    fn play(&self, hand: &Hand, trump_suit: Suit, trick: &Trick) -> Card {
+    fn play(&self, view: &PlayerView) -> Decision<Card> {
+        Decision::Ready(self.choose_card(view))
+    }
+}
+
+impl RandomPlayer {
+    fn choose_card(&self, view: &PlayerView) -> Card {
+        let hand = view.hand();
+        let trump_suit = view.trump_suit().expect("playing w/o trump suit?!");
+        let trick = view.trick().expect("playing w/o trick?!");
         let first_card = match trick.played_cards[trick.first_player.as_index()] {
             Some(card) => card,
             None => {
                 // I'm the first to play!
-                let r = thread_rng().next_u32() as usize % hand.cards.len();
-                return hand.cards[r];
+                let r = self.random_index(hand.len());
+                return hand.cards()[r];
             }
         };
         // Let's see if I have a card with the same suit as the first card
@@ -30,11 +66,11 @@ impl Player for RandomPlayer {
         // Maybe I have a trump card?
         if hand.count_of_suit(trump_suit) > 0 {
             let candidates = hand.cards_of_suit(trump_suit);
-            let r = thread_rng().next_u32() as usize % candidates.len();
+            let r = self.random_index(candidates.len());
             return candidates[r];
         }
         // Otherwise, choose a card at random
-        let r = thread_rng().next_u32() as usize % hand.cards.len();
-        return hand.cards[r];
+        let r = self.random_index(hand.len());
+        return hand.cards()[r];
     }
 }
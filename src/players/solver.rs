@@ -0,0 +1,621 @@
+
+use crate::game::*;
+use crate::cards::*;
+use super::position::*;
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+const SUIT_BITS: u64 = (1 << 13) - 1;
+
+// Solves positions with every hand known (double dummy): the tricks each
+// team ends up with if everyone plays their best, as if all the cards were
+// played even after a team has won 7 tricks. The searches only answer
+// whether players One and Three can take some number of tricks, which
+// prunes much more than searching for the number itself.
+//
+// What the searches learn about the positions at the start of tricks, and
+// after the lead, is kept, so a solver gets faster as it solves related
+// positions. Along with a result goes the set of cards whose ranks decided
+// it, e.g. the ones that won tricks over other cards of their suit; the
+// lower cards of each suit may then be held in any way, so one result
+// covers many positions.
+#[derive(Default)]
+pub struct Solver {
+    table: HashMap<Key, Vec<Entry>, BuildHasherDefault<KeyHasher>>,
+    searched: u64,
+}
+
+// The positions that may share results: the same leader, the same number
+// of cards of each suit in each hand and the same card led, if any
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    lengths: u64,
+    // the card led and how many cards of its suit in the hands are higher,
+    // or 0 at the start of a trick
+    led: u16,
+    leader: u8,
+    trump_suit: u8,
+}
+
+// What is known about the positions of a key where the top cards of each
+// suit are held like in `holders`: the bits of `mask` of the holders of such
+// a position, see `holders()`, are the ones of `holders`
+struct Entry {
+    holders: u128,
+    mask: u128,
+    // bounds on the tricks players One and Three take from here on
+    lower: u8,
+    upper: u8,
+    // whether the rank of the card led decided one of them
+    led_relevant: bool,
+    // the card that decided the last search, to try first next time
+    best: Option<Card>,
+}
+
+impl Key {
+    fn new(pos: &Position, held: u64) -> Self {
+        let mut lengths = 0;
+        for i in 0..4 {
+            let hand = pos.hand(PlayerNumber::from_index(i));
+            for &suit in Suit::all_suits() {
+                lengths = lengths << 4 | hand.count_of_suit(suit) as u64;
+            }
+        }
+        let led = pos.trick()[pos.leader().as_index()].map_or(0, |card| {
+            let higher = held & CardSet::of_suit(card.suit()).bits() & !((2 << card.index()) - 1);
+            (card.index() as u16 + 1) << 4 | higher.count_ones() as u16
+        });
+        Key {
+            lengths,
+            led,
+            leader: pos.leader() as u8,
+            trump_suit: pos.trump_suit() as u8,
+        }
+    }
+}
+
+// The keys are hashed as one number, which only needs a little mixing
+#[derive(Default)]
+struct KeyHasher(u64);
+
+impl Hasher for KeyHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.write_u64(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, x: u8) {
+        self.write_u64(x as u64);
+    }
+
+    fn write_u16(&mut self, x: u16) {
+        self.write_u64(x as u64);
+    }
+
+    fn write_u64(&mut self, x: u64) {
+        // the table picks buckets by the low bits, so the high bits are
+        // mixed back into them
+        let h = (self.0.rotate_left(5) ^ x).wrapping_mul(0x517c_c1b7_2722_0a95);
+        self.0 = h ^ h >> 32;
+    }
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Solver::default()
+    }
+
+    // The number of positions searched so far
+    pub fn nodes(&self) -> u64 {
+        self.searched
+    }
+
+    // The tricks each team has at the end of the hand
+    pub fn solve(&mut self, pos: &Position) -> (u32, u32) {
+        let total = total_tricks(pos);
+        let tricks = self.search_between(pos, 0, total);
+        (tricks, total - tricks)
+    }
+
+    // Each legal card with the tricks the team of the player to move ends up
+    // with after playing it
+    pub fn evaluate_moves(&mut self, pos: &Position) -> Vec<(Card, u32)> {
+        self.evaluate_moves_between(pos, 0, total_tricks(pos))
+    }
+
+    // Like `evaluate_moves`, but only tells apart the numbers of tricks
+    // from `lower` to `upper`: fewer count as `lower` and more as `upper`.
+    // The closer the bounds, the less there is to search.
+    pub fn evaluate_moves_between(&mut self, pos: &Position, lower: u32, upper: u32) -> Vec<(Card, u32)> {
+        let team = pos.turn().team();
+        let total = total_tricks(pos);
+        // the bounds on the tricks of players One and Three
+        let (lower, upper) = match team {
+            Team::PlayersOneAndThree => (lower, upper),
+            Team::PlayersTwoAndFour => (total - upper, total - lower),
+        };
+        let mut values = Vec::new();
+        for group in move_groups(pos) {
+            let mut next = *pos;
+            next.play(group.iter().next().unwrap());
+            let tricks = self.search_between(&next, lower, upper);
+            let value = match team {
+                Team::PlayersOneAndThree => tricks,
+                Team::PlayersTwoAndFour => total - tricks,
+            };
+            values.extend(group.iter().map(|card| (card, value)));
+        }
+        values
+    }
+
+    // The cards that take the most tricks for the player to move
+    pub fn best_cards(&mut self, pos: &Position) -> Vec<Card> {
+        let values = self.evaluate_moves(pos);
+        let best = values.iter().map(|&(_, v)| v).max().unwrap_or(0);
+        values.into_iter().filter(|&(_, v)| v == best).map(|(c, _)| c).collect()
+    }
+
+    // The tricks players One and Three end up with, but at least `lower`
+    // and at most `upper`
+    fn search_between(&mut self, pos: &Position, lower: u32, upper: u32) -> u32 {
+        let total = total_tricks(pos);
+        // the tricks they have taken and the most they can end up with
+        let (mut lower, mut upper) = (pos.tricks().0.clamp(lower, upper), (total - pos.tricks().1).clamp(lower, upper));
+        while lower < upper {
+            let target = (lower + upper + 1) / 2;
+            match self.can_take(pos, target).0 {
+                true => lower = target,
+                false => upper = target - 1,
+            }
+        }
+        lower
+    }
+
+    // Whether players One and Three can end up with `target` tricks, and the
+    // cards whose ranks decided it
+    fn can_take(&mut self, pos: &Position, target: u32) -> (bool, u64) {
+        self.searched += 1;
+        let taken = pos.tricks().0;
+        let remaining = total_tricks(pos) - taken - pos.tricks().1;
+        if taken >= target {
+            return (true, 0);
+        }
+        if taken + remaining < target {
+            return (false, 0);
+        }
+        let live = live_cards(pos);
+        let led = pos.trick()[pos.leader().as_index()];
+        if led.is_none() {
+            let need = (target - taken, taken + remaining + 1 - target);
+            let ((sure13, r13), (sure24, r24)) = sure_tricks(pos, live, need);
+            if taken + sure13 >= target {
+                return (true, r13);
+            }
+            if taken + remaining - sure24 < target {
+                return (false, r24);
+            }
+        }
+        // the results are kept at the start of tricks, and after the lead
+        // while many tricks are left: near the end the searches they save
+        // cost little more than keeping them
+        let seat = (pos.turn().as_index() + 4 - pos.leader().as_index()) % 4;
+        let start = match seat == 0 || seat == 1 && remaining >= 7 {
+            true => {
+                let held = held_cards(pos);
+                Some((Key::new(pos, held), holders(pos, held), held))
+            }
+            false => None,
+        };
+        let mut best = None;
+        if let Some((key, holders, held)) = start {
+            let mut found = None;
+            // the newest entries are the likeliest to decide
+            for entry in self.table.get(&key).into_iter().flatten().rev() {
+                if holders & entry.mask != entry.holders {
+                    continue;
+                }
+                best = best.or(entry.best);
+                if taken + entry.lower as u32 >= target {
+                    found = Some((true, entry.led_relevant, entry.mask));
+                    break;
+                } else if taken + (entry.upper as u32) < target {
+                    found = Some((false, entry.led_relevant, entry.mask));
+                    break;
+                }
+            }
+            if let Some((result, led_relevant, mask)) = found {
+                let relevant = top_cards(held, &counts(mask));
+                return match (led, led_relevant) {
+                    (Some(card), true) => (result, relevant | 1 << card.index()),
+                    _ => (result, relevant),
+                };
+            }
+        }
+        let maximizing = pos.turn().team() == Team::PlayersOneAndThree;
+        // players One and Three need one move that reaches the target, the
+        // others one that keeps them from it
+        let mut result = !maximizing;
+        let mut relevant = 0;
+        let mut decided_by = None;
+        // for each suit, the cards below which any card ends like one that
+        // was tried, see below
+        let mut same_below = [0; 4];
+        for (card, low, high) in Moves::new(pos, live, best) {
+            let suit = high.suit() as usize;
+            if high.index() < same_below[suit] {
+                continue;
+            }
+            let mut next = *pos;
+            next.play(card);
+            let (r, mut rel) = self.can_take(&next, target);
+            if next.led_suit().is_none() {
+                rel |= trick_relevant(pos, card, next.leader());
+            }
+            if r == maximizing {
+                result = maximizing;
+                relevant = rel;
+                decided_by = Some(card);
+                break;
+            }
+            // the other cards of the group were as good as `card` only
+            // because no card came between them, and if the rank of one of
+            // them mattered, the others may not be held here in other
+            // positions
+            let below = CardSet::of_suit(low.suit()).bits() & ((2 << high.index()) - 1);
+            if rel & below != 0 {
+                rel |= 1 << low.index();
+            }
+            // if no card of the suit from `high` down decided it, playing
+            // another card below the ones that did only swaps two cards whose
+            // ranks don't matter
+            let lowest = match rel & CardSet::of_suit(high.suit()).bits() {
+                0 => 64,
+                r => r.trailing_zeros() as usize,
+            };
+            if lowest > high.index() {
+                same_below[suit] = max(same_below[suit], lowest);
+            }
+            relevant |= rel;
+        }
+        if let Some((key, holders, held)) = start {
+            let entry = self.entry(key, holders, mask(&relevant_counts(held, relevant)), remaining);
+            match result {
+                true => entry.lower = max(entry.lower, (target - taken) as u8),
+                false => entry.upper = min(entry.upper, (target - taken - 1) as u8),
+            }
+            entry.led_relevant |= led.map_or(false, |card| relevant >> card.index() & 1 != 0);
+            entry.best = decided_by.or(entry.best);
+        }
+        (result, relevant)
+    }
+
+    // The entry of the positions of the key where the cards of `mask` are
+    // held like in `holders`
+    fn entry(&mut self, key: Key, holders: u128, mask: u128, remaining: u32) -> &mut Entry {
+        let entries = self.table.entry(key).or_default();
+        let holders = holders & mask;
+        let i = match entries.iter().rposition(|e| e.mask == mask && e.holders == holders) {
+            Some(i) => i,
+            None => {
+                entries.push(Entry { holders, mask, lower: 0, upper: remaining as u8, led_relevant: false, best: None });
+                entries.len() - 1
+            }
+        };
+        &mut entries[i]
+    }
+}
+
+// The tricks of the whole hand: the ones taken and the ones left to play
+fn total_tricks(pos: &Position) -> u32 {
+    let leader_cards = pos.hand(pos.leader()).len() as u32;
+    let started = pos.led_suit().is_some() as u32;
+    pos.tricks().0 + pos.tricks().1 + leader_cards + started
+}
+
+// The cards still in the hands or on the table
+fn live_cards(pos: &Position) -> u64 {
+    let mut live = 0;
+    for (i, card) in pos.trick().iter().enumerate() {
+        live |= pos.hand(PlayerNumber::from_index(i)).bits() | card.map_or(0, |c| 1 << c.index());
+    }
+    live
+}
+
+// The cards in the hands
+fn held_cards(pos: &Position) -> u64 {
+    (0..4).fold(0, |held, i| held | pos.hand(PlayerNumber::from_index(i)).bits())
+}
+
+// Who holds the cards of each suit in the hands, two bits for each card
+// from the highest one down, with 32 bits for each suit
+fn holders(pos: &Position, held: u64) -> u128 {
+    let hands = [1, 2, 3].map(|i| pos.hand(PlayerNumber::from_index(i)).bits());
+    let mut holders = 0;
+    for suit in 0..4 {
+        let mut left = held & SUIT_BITS << (suit * 13);
+        let mut shift = 32 * suit;
+        while left != 0 {
+            let i = 63 - left.leading_zeros();
+            let holder = (hands[0] >> i & 1) + 2 * (hands[1] >> i & 1) + 3 * (hands[2] >> i & 1);
+            holders |= (holder as u128) << shift;
+            shift += 2;
+            left ^= 1 << i;
+        }
+    }
+    holders
+}
+
+// The bits of the holders of the top `counts` cards of each suit
+fn mask(counts: &[u32; 4]) -> u128 {
+    let mut mask = 0;
+    for (suit, &count) in counts.iter().enumerate() {
+        mask |= ((1u128 << (2 * count)) - 1) << (32 * suit);
+    }
+    mask
+}
+
+// The reverse of `mask`
+fn counts(mask: u128) -> [u32; 4] {
+    [0, 1, 2, 3].map(|suit| (mask >> (32 * suit)) as u32).map(|bits| bits.count_ones() / 2)
+}
+
+// How many of the top cards of each suit the relevant cards decide: the
+// lowest relevant card of the suit and the cards above it
+fn relevant_counts(live: u64, relevant: u64) -> [u32; 4] {
+    let mut counts = [0; 4];
+    for (suit, count) in counts.iter_mut().enumerate() {
+        let rel = relevant >> (suit * 13) & SUIT_BITS;
+        if rel != 0 {
+            let lowest = rel & rel.wrapping_neg();
+            *count = (live >> (suit * 13) & SUIT_BITS & !(lowest - 1)).count_ones();
+        }
+    }
+    counts
+}
+
+// The reverse of `relevant_counts`: the top cards of each suit
+fn top_cards(live: u64, counts: &[u32; 4]) -> u64 {
+    let mut cards = 0;
+    for (suit, &count) in counts.iter().enumerate() {
+        let mut left = live >> (suit * 13) & SUIT_BITS;
+        for _ in 0..count {
+            let top = 1 << (63 - left.leading_zeros());
+            cards |= top << (suit * 13);
+            left ^= top;
+        }
+    }
+    cards
+}
+
+// The card that won the trick `card` completed, if it won over another card
+// of its suit: then its rank decided the trick.
+fn trick_relevant(pos: &Position, card: Card, winner: PlayerNumber) -> u64 {
+    let mut trick = pos.trick();
+    trick[pos.turn().as_index()] = Some(card);
+    let winning = trick[winner.as_index()].expect("everyone has played");
+    match trick.iter().flatten().filter(|c| c.suit() == winning.suit()).count() > 1 {
+        true => 1 << winning.index(),
+        false => 0,
+    }
+}
+
+// Whether no live card comes between two cards of a suit, `low` being the
+// lower one, which makes them as good as each other
+fn touching(low: Card, high: Card, live: u64) -> bool {
+    let between = ((1 << high.index()) - 1) & !((1 << (low.index() + 1)) - 1);
+    low.suit() == high.suit() && live & between == 0
+}
+
+// The legal cards in groups that are as good as each other
+fn move_groups(pos: &Position) -> Vec<CardSet> {
+    let live = live_cards(pos);
+    let mut groups: Vec<CardSet> = Vec::new();
+    let mut last: Option<Card> = None;
+    for card in pos.legal_moves() {
+        match (last, groups.last_mut()) {
+            (Some(l), Some(group)) if touching(l, card, live) => { group.insert(card); },
+            _ => groups.push(Some(card).into_iter().collect()),
+        }
+        last = Some(card);
+    }
+    groups
+}
+
+// One card of each group of legal cards, with the lowest and the highest
+// card of the group. The likely best come first: `first` if it is legal,
+// then a cheap winner of the trick, or a low card if the partner is winning
+// it or nothing wins it. Leads go from the highest card down. This is
+// called for every position searched and the first card usually decides
+// it, so the groups are only weighed up front, and each next card is picked
+// when it is needed.
+struct Moves {
+    // the weight, the card to play, and the lowest and highest card
+    groups: [(u32, u32, u32, u32); 13],
+    n: usize,
+}
+
+impl Moves {
+    fn new(pos: &Position, live: u64, first: Option<Card>) -> Self {
+        let trump = pos.trump_suit() as u32;
+        let winning = pos.winning_card();
+        let partner_wins = winning.map_or(false, |(winner, _)| winner.team() == pos.turn().team());
+        let first = first.map_or(64, |c| c.index() as u32);
+        let legal = pos.legal_moves().bits();
+        let mut p = pos.turn();
+        p.increment();
+        p.increment();
+        let partner = pos.hand(p).bits();
+        let mut moves = Moves { groups: [(0, 0, 0, 0); 13], n: 0 };
+        for suit in 0..4 {
+            let cards = legal >> (suit * 13) & SUIT_BITS;
+            if cards == 0 {
+                continue;
+            }
+            let live = live >> (suit * 13) & SUIT_BITS;
+            let trumps = (suit == trump) as u32 * 13;
+            let top = 63 - live.leading_zeros();
+            let partner_top = partner >> (suit * 13 + top) & 1 != 0;
+            // the lowest rank that wins the trick
+            let winner = match winning {
+                None => 0,
+                Some(_) if partner_wins => 0,
+                Some((_, w)) if w.suit() as u32 == suit => w.index() as u32 % 13 + 1,
+                Some((_, w)) if suit == trump && w.suit() as u32 != trump => 0,
+                Some(_) => 13,
+            };
+            let mut left = cards;
+            while left != 0 {
+                let low = left.trailing_zeros();
+                // the group goes on while the next live card up is ours
+                let above = live & !((2 << low) - 1);
+                let run = match above & !cards {
+                    0 => above,
+                    gap => above & ((1 << gap.trailing_zeros()) - 1),
+                };
+                let high = match run {
+                    0 => low,
+                    _ => 63 - run.leading_zeros(),
+                };
+                left &= !((2 << high) - 1);
+                let (low, high) = (suit * 13 + low, suit * 13 + high);
+                let value = |c: u32| trumps + c % 13;
+                moves.groups[moves.n] = match winning {
+                    _ if first >= low && first <= high => (0, high, low, high),
+                    None if high % 13 == top => (10 - trumps / 13, high, low, high),
+                    None if partner_top => (20 + value(low), low, low, high),
+                    None => (50 + value(low), low, low, high),
+                    Some(_) if !partner_wins && high % 13 == top && high % 13 >= winner => (1, high, low, high),
+                    Some(_) if partner_wins || low % 13 >= winner => (1 + value(low), low, low, high),
+                    Some(_) => (50 + value(low), low, low, high),
+                };
+                moves.n += 1;
+            }
+        }
+        moves
+    }
+}
+
+impl Iterator for Moves {
+    type Item = (Card, Card, Card);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let groups = &mut self.groups[..self.n];
+        let i = (0..groups.len()).min_by_key(|&i| groups[i].0)?;
+        let (_, card, low, high) = groups[i];
+        self.n -= 1;
+        self.groups.swap(i, self.n);
+        let to_card = |i: u32| Card::from_index(i as usize).unwrap();
+        Some((to_card(card), to_card(low), to_card(high)))
+    }
+}
+
+// How many of the highest cards of a suit, the bits of `live`, `hand`
+// holds, up to `limit`, and those cards. The bounds that count them only get
+// better when the hand holds more, so no other card matters to them.
+fn top_run(live: u64, hand: u64, limit: u32) -> (u32, u64) {
+    let (mut run, mut left, mut cards) = (0, live, 0);
+    while left != 0 && run < limit {
+        let top = 1 << (63 - left.leading_zeros());
+        if hand & top == 0 {
+            break;
+        }
+        run += 1;
+        cards |= top;
+        left ^= top;
+    }
+    (run, cards)
+}
+
+// The tricks each team is sure to take at the start of a trick, see
+// `quick_tricks` and `trump_tricks`, and the cards that decide them. Only
+// the tricks a team needs are counted, so that as few cards as possible
+// decide them.
+fn sure_tricks(pos: &Position, live: u64, need: (u32, u32)) -> ((u32, u64), (u32, u64)) {
+    let need_leader = match pos.leader().team() {
+        Team::PlayersOneAndThree => need.0,
+        Team::PlayersTwoAndFour => need.1,
+    };
+    let quick = quick_tricks(pos, live, need_leader);
+    let t13 = trump_tricks(pos, live, Team::PlayersOneAndThree, need.0);
+    let t24 = trump_tricks(pos, live, Team::PlayersTwoAndFour, need.1);
+    match pos.leader().team() {
+        Team::PlayersOneAndThree => (max_by_tricks(t13, quick), t24),
+        Team::PlayersTwoAndFour => (t13, max_by_tricks(t24, quick)),
+    }
+}
+
+fn max_by_tricks(a: (u32, u64), b: (u32, u64)) -> (u32, u64) {
+    match a.0 >= b.0 {
+        true => a,
+        false => b,
+    }
+}
+
+// Every card is played, so a player's trumps that are higher than anyone
+// else's win a trick each. The team's trumps that are higher than the other
+// team's win at least every other trick, when partners play two in one.
+fn trump_tricks(pos: &Position, live: u64, team: Team, need: u32) -> (u32, u64) {
+    let live = live & CardSet::of_suit(pos.trump_suit()).bits();
+    let (a, b) = match team {
+        Team::PlayersOneAndThree => (PlayerNumber::One, PlayerNumber::Three),
+        Team::PlayersTwoAndFour => (PlayerNumber::Two, PlayerNumber::Four),
+    };
+    let (a, b) = (pos.hand(a).bits(), pos.hand(b).bits());
+    let (team_top, relevant) = top_run(live, a | b, (2 * need).saturating_sub(1));
+    let player_top = max_by_tricks(top_run(live, a, need), top_run(live, b, need));
+    max_by_tricks(player_top, ((team_top + 1) / 2, relevant))
+}
+
+// The tricks the leader's team is sure to take: the leader can cash their
+// top trumps, and once those have drawn the other team's trumps, the top
+// cards of the other suits too. Then the leader can still lead a low card
+// of a suit to the partner's top cards.
+fn quick_tricks(pos: &Position, live: u64, need: u32) -> (u32, u64) {
+    let trump = pos.trump_suit();
+    let leader = pos.leader();
+    let mut right = leader;
+    right.increment();
+    let mut partner = right;
+    partner.increment();
+    let mut left = partner;
+    left.increment();
+    let (hand, partner) = (pos.hand(leader), pos.hand(partner));
+    let top = |suit: Suit, hand: CardSet, limit: u32| top_run(live & CardSet::of_suit(suit).bits(), hand.bits(), limit);
+    let (trumps, mut relevant) = top(trump, hand, 13);
+    let (left, right) = (pos.hand(left), pos.hand(right));
+    let their_trumps = max(left.count_of_suit(trump), right.count_of_suit(trump)) as u32;
+    if trumps >= need {
+        return top(trump, hand, need);
+    }
+    // when the trumps can't all be drawn, a suit is cashed only as long as
+    // the others with trumps follow it
+    let drawn = their_trumps <= trumps;
+    let follows = |suit: Suit| {
+        let length = |h: CardSet| match h.count_of_suit(trump) > 0 && !drawn {
+            true => h.count_of_suit(suit) as u32,
+            false => 13,
+        };
+        min(length(left), length(right))
+    };
+    let (mut tricks, mut partners) = (trumps, (0, 0));
+    for &suit in Suit::all_suits().iter().filter(|&&s| s != trump) {
+        let (run, r) = top(suit, hand, min(need - tricks, follows(suit)));
+        tricks += run;
+        relevant |= r;
+        if tricks == need {
+            return (tricks, relevant);
+        }
+        if drawn && run == 0 && hand.count_of_suit(suit) > 0 {
+            partners = max_by_tricks(partners, top(suit, partner, need - tricks));
+        }
+    }
+    (min(tricks + partners.0, hand.len() as u32), relevant | partners.1)
+}